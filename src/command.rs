@@ -25,40 +25,184 @@ impl Command {
     }
 }
 
+/// Parses the user's input into a command.  See `normalize` for the handling of
+/// punctuation.
 pub fn parse(world: &World, input: &str) -> Result<Command, String> {
     // FIRST, remove extraneous characters.
     let input = input.trim();
+    let is_debug = input.starts_with('!');
+    let body = if is_debug { &input[1..] } else { input };
+    let text = normalize(body, is_debug)?;
+
+    // NEXT, split into words; a quoted phrase is a single word.
+    let mut raw_words = split_words(&text);
+
+    // NEXT, expand the first word if it's an alias.
+    if !is_debug && !raw_words.is_empty() {
+        if let Some(expansion) = world.aliases.get(&raw_words[0]) {
+            let mut expanded: Vec<String> =
+                expansion.split_whitespace().map(|w| w.to_string()).collect();
+            expanded.extend(raw_words.drain(1..));
+            raw_words = expanded;
+        }
+    }
+
+    // NEXT, strip articles, join known multi-word nouns, and translate synonyms.
+    raw_words.retain(|w| !matches!(w.as_str(), "a" | "an" | "the"));
+    let raw_words = join_phrases(world, raw_words);
+    let mut words: Vec<String> = Vec::new();
+
+    for word in raw_words {
+        if let Some(canon) = world.synonyms.get(&word) {
+            words.push(canon.to_string());
+        } else {
+            words.push(word);
+        }
+    }
+
+    // NEXT, return the result.
+    Ok(Command::new(input, words))
+}
+
+/// Normalizes the punctuation in the body of a command:
+///
+/// * Commas are removed, as is '!' (except in debugging commands, where it can be
+///   part of a verb, e.g., "!go!").
+/// * A '.' or '?' ends the command; anything but whitespace after it is an error,
+///   as multiple commands aren't yet supported.
+/// * Apostrophes within words are kept, e.g., "john's"; others are removed.
+/// * Repeated whitespace is collapsed to a single space.
+fn normalize(body: &str, is_debug: bool) -> Result<String, String> {
     let mut text = String::new();
+    let mut chars = body.chars().peekable();
+    let mut prev: Option<char> = None;
 
-    for c in input.chars() {
+    while let Some(c) = chars.next() {
         match c {
-            ',' | '!' => {}
-            '.' => {
-                return Err("Input contains '.'; multiple commands not yet support.".into());
+            ',' => {}
+            '!' if !is_debug => {}
+            '.' | '?' => {
+                if chars.any(|c| !c.is_whitespace()) {
+                    return Err(
+                        "Input contains multiple sentences; multiple commands not yet supported."
+                            .into(),
+                    );
+                }
+                break;
+            }
+            '\'' => {
+                let within = prev.is_some_and(char::is_alphanumeric)
+                    && chars.peek().is_some_and(|c| c.is_alphanumeric());
+                if within {
+                    text.push(c);
+                }
             }
             _ => text.push(c),
         }
+        prev = Some(c);
     }
 
-    // NEXT, split into words
-    let raw_words: Vec<&str> = text.split_whitespace().collect();
+    Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
 
-    // NEXT, strip articles and translate synonyms.
-    let mut words: Vec<String> = Vec::new();
+/// Splits normalized text into words.  Text within double quotes is a single
+/// word, e.g., "brass lantern".
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
 
-    for word in raw_words {
-        match word {
-            "a" | "an" | "the" => (),
-            _ => {
-                if let Some(canon) = world.synonyms.get(word) {
-                    words.push(canon.to_string());
-                } else {
-                    words.push(word.to_string());
+    for c in text.chars() {
+        match c {
+            '"' => {
+                if !word.is_empty() {
+                    words.push(word.trim().to_string());
+                    word.clear();
+                }
+                quoted = !quoted;
+            }
+            ' ' if !quoted => {
+                if !word.is_empty() {
+                    words.push(word.clone());
+                    word.clear();
                 }
             }
+            _ => word.push(c),
         }
     }
 
-    // NEXT, return the result.
-    Ok(Command::new(input, words))
+    if !word.trim().is_empty() {
+        words.push(word.trim().to_string());
+    }
+
+    words.retain(|w| !w.is_empty());
+    words
+}
+
+/// Joins runs of words that form a known multi-word noun, e.g., "brass lantern",
+/// into a single word.  The longest match wins.
+fn join_phrases(world: &World, words: Vec<String>) -> Vec<String> {
+    let phrases: Vec<&String> = world
+        .things
+        .values()
+        .flat_map(|thingc| thingc.phrases.iter())
+        .collect();
+
+    if phrases.is_empty() {
+        return words;
+    }
+
+    let mut result = Vec::new();
+    let mut i = 0;
+
+    while i < words.len() {
+        let len = (2..=words.len() - i)
+            .rev()
+            .find(|n| {
+                let candidate = words[i..i + n].join(" ");
+                phrases.iter().any(|p| **p == candidate)
+            })
+            .unwrap_or(1);
+
+        result.push(words[i..i + len].join(" "));
+        i += len;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(input: &str) -> Vec<String> {
+        parse(&World::new(), input).unwrap().words
+    }
+
+    #[test]
+    fn normalize_punctuation() {
+        assert_eq!(normalize("look,", false), Ok("look".into()));
+        assert_eq!(normalize("get the note!", false), Ok("get the note".into()));
+        assert_eq!(normalize("where am I?", false), Ok("where am I".into()));
+        assert_eq!(normalize("look.  ", false), Ok("look".into()));
+        assert_eq!(normalize("go!  north", true), Ok("go! north".into()));
+        assert_eq!(normalize("'hat'", false), Ok("hat".into()));
+        assert!(normalize("look. north", false).is_err());
+    }
+
+    #[test]
+    fn parse_words() {
+        assert_eq!(words("look,"), vec!["look"]);
+        assert_eq!(words("get the note!"), vec!["get", "note"]);
+        assert_eq!(words("examine   john's hat"), vec!["examine", "john's", "hat"]);
+    }
+
+    #[test]
+    fn quoted_words() {
+        assert_eq!(
+            split_words("examine \"brass lantern\""),
+            vec!["examine", "brass lantern"]
+        );
+        assert_eq!(split_words("say \" hi  "), vec!["say", "hi"]);
+    }
 }
@@ -3,12 +3,72 @@
 
 use textwrap::Wrapper;
 
+/// The fence that begins and ends a preformatted block.
+const FENCE: &str = "```";
+
 /// Wraps a text string for display to the console.  The string is wrapped to fit within
 /// the console terminal width.  The string is broken at explicit newlines.
+///
+/// Lines that begin with white space are indented; if they are wrapped, the continuation
+/// lines are given a hanging indent so that they line up with the text of the first line.
+/// If the line's first word is a list marker, e.g., "*" or "1.", the continuation lines
+/// line up with the text following the marker.
+///
+/// Lines between fence lines ("```") are preformatted, and are output verbatim; the
+/// fence lines themselves are omitted.
 pub fn conwrap(text: &str) -> String {
-    let wrapper = Wrapper::with_termwidth();
+    let mut lines: Vec<String> = Vec::new();
+    let mut verbatim = false;
+
+    for line in text.split('\n') {
+        if line == FENCE {
+            verbatim = !verbatim;
+            continue;
+        }
+
+        if verbatim {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let indent = hanging_indent(line);
+        let wrapper = Wrapper::with_termwidth().subsequent_indent(&indent);
+        lines.push(wrapper.fill(line));
+    }
+
+    lines.join("\n")
+}
+
+/// Computes the hanging indent for a line of text: the line's leading white space,
+/// plus the width of its list marker, if any.
+fn hanging_indent(line: &str) -> String {
+    let text = line.trim_start();
+    let leading = line.len() - text.len();
+
+    if leading == 0 {
+        return String::new();
+    }
+
+    let marker = text.split_whitespace().next().unwrap_or("");
+    let width = if is_list_marker(marker) {
+        leading + marker.len() + 1
+    } else {
+        leading
+    };
+
+    " ".repeat(width)
+}
+
+/// Is the word a list marker, i.e., a bullet ("*" or "-") or a number followed by a
+/// period?
+fn is_list_marker(word: &str) -> bool {
+    if word == "*" || word == "-" {
+        return true;
+    }
 
-    wrapper.fill(text)
+    word.len() > 1
+        && word.ends_with('.')
+        && word[..word.len() - 1].chars().all(|c| c.is_ascii_digit())
 }
 
 /// Reformats the input string using conmark syntax:
@@ -16,6 +76,9 @@ pub fn conwrap(text: &str) -> String {
 /// * The string is trimmed.
 /// * Explicit newlines are replaced with blanks.
 /// * Pipe characters ("|") are replaced with newlines.
+/// * A pipe followed by a hyphen ("|-") begins a bulleted list item on a new line.
+/// * Text between two fences ("```") is preformatted: it is passed through verbatim,
+///   and the fences are placed on lines of their own for `conwrap()`.
 ///
 /// The reason for this syntax is as follows:
 ///
@@ -24,15 +87,57 @@ pub fn conwrap(text: &str) -> String {
 /// * But this use case is the opposite.  You usually don't want the explicit line breaks, but
 ///   in rare cases you'll want to escape from that.
 /// * Plus, it builds in a mechanism where we can add more interesting stuff in the long run.
+///
+/// Lines beginning with white space retain their indentation; see `conwrap()`.
 pub fn confmt(text: &str) -> String {
     let mut result = String::new();
+    let chars: Vec<char> = text.trim().chars().collect();
+    let fence: Vec<char> = FENCE.chars().collect();
+    let mut verbatim = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        // FIRST, handle fences, which always go on lines of their own.
+        if chars[i..].starts_with(&fence) {
+            i += fence.len();
+
+            if verbatim {
+                if !result.ends_with('\n') {
+                    result.push('\n');
+                }
+                result.push_str(FENCE);
+                if i < chars.len() {
+                    result.push('\n');
+                    if chars[i] == '\n' {
+                        i += 1;
+                    }
+                }
+            } else {
+                let len = result.trim_end_matches(' ').len();
+                result.truncate(len);
+                if !result.is_empty() && !result.ends_with('\n') {
+                    result.push('\n');
+                }
+                result.push_str(FENCE);
+            }
+
+            verbatim = !verbatim;
+            continue;
+        }
 
-    for char in text.trim().chars() {
-        match char {
+        // NEXT, handle normal text.
+        match chars[i] {
+            c if verbatim => result.push(c),
             '\n' => result.push(' '),
+            '|' if chars.get(i + 1) == Some(&'-') => {
+                i += 1;
+                result.push_str("\n  * ");
+            }
             '|' => result.push('\n'),
             c => result.push(c),
         }
+
+        i += 1;
     }
 
     result
@@ -64,4 +169,29 @@ mod tests {
     fn test_confmt_newline() {
         assert_eq!(confmt("ab|cd"), "ab\ncd");
     }
+
+    #[test]
+    fn test_confmt_bullet() {
+        assert_eq!(confmt("ab|-cd|-ef"), "ab\n  * cd\n  * ef");
+    }
+
+    #[test]
+    fn test_confmt_fence() {
+        assert_eq!(confmt("Map:\n```\n a  b\n |\n```\nDone."), "Map:\n```\n a  b\n |\n```\nDone.");
+    }
+
+    #[test]
+    fn test_conwrap_fence() {
+        let long = "-".repeat(300);
+        let text = format!("Map:\n```\n{}\n```\nDone.", long);
+        assert_eq!(conwrap(&text), format!("Map:\n{}\nDone.", long));
+    }
+
+    #[test]
+    fn test_hanging_indent() {
+        assert_eq!(hanging_indent("ab cd"), "");
+        assert_eq!(hanging_indent("  ab cd"), "  ");
+        assert_eq!(hanging_indent("  * ab cd"), "    ");
+        assert_eq!(hanging_indent("   12. ab cd"), "       ");
+    }
 }
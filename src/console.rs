@@ -3,6 +3,10 @@
 //! to output paragraphs of text.
 
 use crate::conmark::*;
+use crate::types::ProseBuffer;
+use crate::visual::OutputEvent;
+use crate::visual::OutputSink;
+use crate::para;
 
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
@@ -12,6 +16,19 @@ pub struct Console {
     rusty: Editor<()>,
 }
 
+/// The result of reading a line of input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    /// A non-empty line of input
+    Line(String),
+
+    /// The user pressed ^C
+    Interrupt,
+
+    /// The user pressed ^D, or the input ended
+    Eof,
+}
+
 impl Console {
     /// Creates the console input abstraction.
     pub fn new() -> Console {
@@ -21,8 +38,9 @@ impl Console {
     }
 
     /// Read a non-empty line from the console, using the given prompt.
-    /// Ignores empty lines; halts on ^C, ^D.
-    pub fn readline(&mut self, prompt: &str) -> String {
+    /// Ignores empty lines; returns Interrupt on ^C and Eof on ^D, leaving the
+    /// caller to decide what to do.
+    pub fn readline(&mut self, prompt: &str) -> Input {
         loop {
             match self.rusty.readline(prompt) {
                 Ok(line) => {
@@ -31,19 +49,67 @@ impl Console {
                         continue;
                     } else {
                         self.rusty.add_history_entry(line);
-                        return line.to_string();
+                        return Input::Line(line.to_string());
                     }
                 }
-                Err(ReadlineError::Interrupted) => break,
-                Err(ReadlineError::Eof) => break,
+                Err(ReadlineError::Interrupted) => return Input::Interrupt,
+                Err(ReadlineError::Eof) => return Input::Eof,
                 Err(err) => {
                     println!("Input Error: {:?}", err);
                     continue;
                 }
             }
         }
+    }
+
+    /// Asks a yes/no question, returning true if the answer begins with "y".
+    /// Interrupts and end of input count as "no" and "yes", respectively.
+    pub fn confirm(&mut self, question: &str) -> bool {
+        match self.readline(question) {
+            Input::Line(answer) => answer.to_lowercase().starts_with('y'),
+            Input::Interrupt => false,
+            Input::Eof => true,
+        }
+    }
+}
 
-        ::std::process::exit(0);
+/// An OutputSink that displays the visual system's output on the console.
+pub struct ConsoleSink;
+
+impl OutputSink for ConsoleSink {
+    fn emit(&mut self, event: OutputEvent) {
+        match event {
+            OutputEvent::Room(name, Some(description)) => {
+                let mut buff = ProseBuffer::new();
+                buff.puts(&name);
+                buff.newline();
+                buff.puts(&description);
+                para(&buff.get());
+            }
+            OutputEvent::Room(name, None) => para(&name),
+            OutputEvent::ObjectList(names) => {
+                para!("You see: {}.", names.join(", "));
+            }
+            OutputEvent::Inventory(carried, bound) => {
+                let mut clauses: Vec<String> = Vec::new();
+                if !carried.is_empty() {
+                    clauses.push(format!("You have: {}.", carried.join(", ")));
+                }
+                if !bound.is_empty() {
+                    clauses.push(format!("You can't let go of: {}.", bound.join(", ")));
+                }
+
+                if clauses.is_empty() {
+                    para("You aren't carrying anything.");
+                } else {
+                    para!("{}\n", clauses.join("  "));
+                }
+            }
+            OutputEvent::Body(text)
+            | OutputEvent::Action(text)
+            | OutputEvent::Error(text)
+            | OutputEvent::Info(text) => para(&text),
+        }
     }
 }
 
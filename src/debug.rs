@@ -12,6 +12,51 @@ pub fn list_world(world: &World) {
     }
 }
 
+/// List all rooms in the world, with their names
+pub fn list_rooms(world: &World) {
+    for id in world.tags.keys() {
+        if let Some(roomc) = world.rooms.get(id) {
+            println!("[{}] {}: {}", id, world.tag(*id), roomc.name);
+        }
+    }
+}
+
+/// List all verbs in the world, each canonical verb followed by its synonyms.
+pub fn list_verbs(world: &World) {
+    let mut canons: Vec<&String> = world.verbs.iter()
+        .filter(|verb| world.synonyms.get(*verb) == Some(verb))
+        .collect();
+    canons.sort();
+
+    for canon in canons {
+        let mut syns: Vec<&str> = world.verbs.iter()
+            .filter(|verb| *verb != canon && world.synonyms.get(*verb) == Some(canon))
+            .map(|verb| verb.as_str())
+            .collect();
+        syns.sort();
+
+        if syns.is_empty() {
+            println!("{}", canon);
+        } else {
+            println!("{}: {}", canon, syns.join(", "));
+        }
+    }
+}
+
+/// Print counts of the world's entities and components, and the current clock.
+pub fn world_stats(world: &World) {
+    let guards = world.rules.values().filter(|rulec| rulec.is_guard).count();
+    let flags: usize = world.flag_sets.values().map(|fc| fc.len()).sum();
+
+    println!("Entities: {}", world.tags.len());
+    println!("Rooms:    {}", world.rooms.len());
+    println!("Things:   {}", world.things.len());
+    println!("Rules:    {}", world.rules.len() - guards);
+    println!("Guards:   {}", guards);
+    println!("Flags:    {}", flags);
+    println!("Clock:    {}", world.clock);
+}
+
 /// Dump all entities in the world
 #[allow(dead_code)]
 pub fn dump_world(world: &World) {
@@ -45,6 +90,9 @@ pub fn dump_entity(world: &World, id: ID) {
     if let Some(thingc) = &world.things.get(&id) {
         println!("  Thing name: {}", thingc.name);
         println!("    Noun: {}", thingc.noun);
+        for phrase in &thingc.phrases {
+            println!("    Phrase: {}", phrase);
+        }
     }
 
     // NEXT, if it's a room display the room info.
@@ -55,8 +103,9 @@ pub fn dump_entity(world: &World, id: ID) {
                 Room(id) => {
                     println!("    Link: {:?} to [{}] {}", dir, id, world.tag(*id));
                 },
-                DeadEnd(prose) => {
-                    println!("    Link: {:?} to DeadEnd: {}", dir, prose);
+                DeadEnd(prose, advertised) => {
+                    let kind = if *advertised { "Apparent DeadEnd" } else { "DeadEnd" };
+                    println!("    Link: {:?} to {}: {}", dir, kind, prose);
                 }
             }
         }
@@ -64,15 +113,17 @@ pub fn dump_entity(world: &World, id: ID) {
 
     // NEXT, if it's a rule display its actions.
     if let Some(rulec) = &world.rules.get(&id) {
+        if let Some(limit) = rulec.limit {
+            println!("  Fired: {} of {} times", rulec.fire_count, limit);
+        }
+        if rulec.cooldown > 0 {
+            println!("  Cooldown: {} turns, last fired: {:?}", rulec.cooldown, rulec.last_fired);
+        }
         rulec.script.dump("  ");
     }
 
     // NEXT, display its flags, if any.
-    if let Some(flagc) = &world.flag_sets.get(&id) {
-        for flag in flagc.iter() {
-            println!("  Flag: {:?}", flag);
-        }
-    }
+    dump_flags(world, id);
 
     // NEXT, display its inventory, if any.
     if let Some(invc) = world.inventories.get(&id) {
@@ -92,3 +143,32 @@ pub fn dump_entity(world: &World, id: ID) {
         }
     }
 }
+
+/// Lists the rules whose events or scripts refer to the given entity, with
+/// their actions.
+pub fn rules_for(world: &World, id: ID) {
+    let tag = world.tag(id);
+    let mut count = 0;
+
+    for (rid, rulec) in &world.rules {
+        if rulec.event.ids().contains(&id) || rulec.script.mentions(world, &tag) {
+            let kind = if rulec.is_guard { "Guard" } else { "Rule" };
+            println!("[{}] {}: {} on {:?}", rid, world.tag(*rid), kind, rulec.event);
+            rulec.script.dump("  ");
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        println!("No rules refer to [{}] {}", id, tag);
+    }
+}
+
+/// Dump the entity's flags, if it has any.
+pub fn dump_flags(world: &World, id: ID) {
+    if let Some(flagc) = &world.flag_sets.get(&id) {
+        for flag in flagc.iter() {
+            println!("  Flag: {:?}", flag);
+        }
+    }
+}
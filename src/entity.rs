@@ -1,4 +1,13 @@
 //! The Entity Data Type and Builder
+//!
+//! An entity is just an ID; its data lives in the components defined in the
+//! `*_component` submodules, each of which is stored in its own map in the `World`.
+//! To add a new component:
+//!
+//! * Define the component struct in a new `*_component` module, and declare it here.
+//! * Add a map for it to `World`, with a `has_*` or `is_*` query.
+//! * Add a way to attach it to the relevant builders in `world_builder`.
+//! * If scenario hooks will need to edit it, add it to the relevant `*_view` modules.
 
 pub mod flag_set_component;
 pub mod inventory_component;
@@ -6,9 +15,13 @@ pub mod location_component;
 pub mod player_component;
 pub mod prose_component;
 pub mod room_component;
+#[allow(dead_code)] // Available to scenario hooks.
+pub mod room_view;
 pub mod rule_component;
 pub mod tag_component;
 pub mod thing_component;
+#[allow(dead_code)] // Available to scenario hooks.
+pub mod thing_view;
 
 /// The entity ID type: an integer.
 pub type ID = usize;
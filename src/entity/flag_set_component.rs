@@ -4,7 +4,7 @@ use crate::types::Flag;
 use std::collections::HashSet;
 
 /// Inventories of Things
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct FlagSetComponent {
     /// A set of things in the inventory.  We use a BTreeSet so that we preserve the order
     /// in which things were added.
@@ -20,7 +20,6 @@ impl FlagSetComponent {
     }
 
     /// Gets the number of flags in the set.
-    #[allow(dead_code)]
     pub fn len(&self) -> usize {
         self.set.len()
     }
@@ -4,7 +4,7 @@ use crate::entity::ID;
 use std::collections::BTreeSet;
 
 /// Inventories of Things
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct InventoryComponent {
     /// A set of things in the inventory.  We use a BTreeSet so that we preserve the order
     /// in which things were added.
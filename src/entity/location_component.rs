@@ -6,7 +6,7 @@ use crate::entity::ID;
 use crate::world::LIMBO;
 
 /// Inventories of Things
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct LocationComponent {
     /// The location of this entity, for entities that can have a location.
     pub id: ID,
@@ -1,15 +1,17 @@
 //! Player Data Module
 
+use crate::entity::ID;
+
 /// Information specific to Player Entities
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct PlayerComponent {
-    // None yet; at present, this component serves only as a marker that the entity
-// is a (the) player.  This will eventually change.
+    /// The room the player was in before the current one, if any.
+    pub prev_loc: Option<ID>,
 }
 
 impl PlayerComponent {
     /// Create a new PlayerComponent
     pub fn new() -> Self {
-        Self {}
+        Self { prev_loc: None }
     }
 }
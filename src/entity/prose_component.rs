@@ -8,8 +8,9 @@ use crate::world::World;
 use std::collections::HashMap;
 use std::fmt;
 
-/// A hook to convert an entity into prose.
-/// We define this struct because we can't add traits to EntityStringHook.
+/// A hook to convert an entity into prose, by writing it into a ProseBuffer.
+/// We define this struct because we can't add traits to EntityProseHook.
+/// (For simple, unchanging text, use Prose::Prose instead.)
 #[derive(Clone)]
 pub struct ProseHook {
     hook: EntityProseHook,
@@ -12,6 +12,9 @@ pub struct RoomComponent {
 
     /// Links from this room to other rooms.
     pub links: HashMap<Dir, LinkDest>,
+
+    /// Custom messages for directions that have no link, e.g., "The cliff is too steep."
+    pub no_exits: HashMap<Dir, String>,
 }
 
 impl RoomComponent {
@@ -20,6 +23,7 @@ impl RoomComponent {
         RoomComponent {
             name: name.into(),
             links: HashMap::new(),
+            no_exits: HashMap::new(),
         }
     }
 }
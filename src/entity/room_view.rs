@@ -0,0 +1,70 @@
+//! Room View
+//! A RoomView is a checked-out copy of a room's components.  It can be mutated freely
+//! and then saved back into the world.
+
+use crate::entity::flag_set_component::FlagSetComponent;
+use crate::entity::room_component::RoomComponent;
+use crate::entity::ID;
+use crate::world::World;
+
+/// A mutable view of a room entity.
+#[derive(Debug, Clone)]
+pub struct RoomView {
+    /// The room's ID
+    pub id: ID,
+
+    /// The room's room component
+    pub room: RoomComponent,
+
+    /// The room's flags
+    pub flags: FlagSetComponent,
+}
+
+impl RoomView {
+    /// Checks out a view of the given room.
+    ///
+    /// * Panics if the entity is not a room.
+    pub fn from(world: &World, id: ID) -> Self {
+        assert!(world.is_room(id), "Not a room: [{}]", id);
+
+        Self {
+            id,
+            room: world.rooms[&id].clone(),
+            flags: world.flag_sets.get(&id).cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Saves the view's components back into the world.
+    pub fn save(self, world: &mut World) {
+        world.rooms.insert(self.id, self.room);
+        world.flag_sets.insert(self.id, self.flags);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Dir;
+    use crate::types::Flag::User;
+    use crate::types::LinkDest;
+    use crate::world_builder::WorldBuilder;
+
+    #[test]
+    fn from_and_save() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.room("there", "There");
+        let mut world = wb.world();
+        let here = world.lookup("here");
+        let there = world.lookup("there");
+
+        let mut view = RoomView::from(&world, here);
+        view.room.links.insert(Dir::North, LinkDest::Room(there));
+        view.flags.set(User("VISITED"));
+        view.save(&mut world);
+
+        assert!(matches!(world.rooms[&here].links.get(&Dir::North), Some(LinkDest::Room(id)) if *id == there));
+        assert!(world.has_flag(here, User("VISITED")));
+    }
+}
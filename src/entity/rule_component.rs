@@ -1,8 +1,10 @@
 //! Rule Data
 
+use crate::entity::ID;
 use crate::script::Script;
 use crate::types::Event;
 use crate::types::RulePredicate;
+use crate::types::Time;
 
 /// Game rules: actions taken when a predicate is met
 #[derive(Clone)]
@@ -11,6 +13,28 @@ pub struct RuleComponent {
     pub is_guard: bool,
     pub predicate: RulePredicate,
     pub script: Script,
+
+    /// Rules with higher priority fire before rules with lower priority; rules
+    /// with the same priority fire in order of creation.
+    pub priority: i32,
+
+    /// The entity that owns the rule, if any.  An owned rule is active only while its
+    /// owner is in the world, i.e., not in LIMBO.  The rule's predicate and script
+    /// can refer to the owner; see `rule::owner()`.
+    pub owner: Option<ID>,
+
+    /// The maximum number of times the rule can fire, if any.
+    pub limit: Option<u32>,
+
+    /// The number of times the rule has fired.
+    pub fire_count: u32,
+
+    /// The number of turns that must pass after the rule fires before it can
+    /// fire again.
+    pub cooldown: Time,
+
+    /// The game clock when the rule last fired, if it has.
+    pub last_fired: Option<Time>,
 }
 
 impl RuleComponent {
@@ -21,6 +45,12 @@ impl RuleComponent {
             is_guard: false,
             predicate: &|_| true,
             script: Script::new(),
+            priority: 0,
+            owner: None,
+            limit: None,
+            fire_count: 0,
+            cooldown: 0,
+            last_fired: None,
         }
     }
 
@@ -31,6 +61,12 @@ impl RuleComponent {
             is_guard: false,
             predicate,
             script: Script::new(),
+            priority: 0,
+            owner: None,
+            limit: None,
+            fire_count: 0,
+            cooldown: 0,
+            last_fired: None,
         }
     }
 
@@ -41,6 +77,12 @@ impl RuleComponent {
             is_guard: true,
             predicate,
             script: Script::new(),
+            priority: 0,
+            owner: None,
+            limit: None,
+            fire_count: 0,
+            cooldown: 0,
+            last_fired: None,
         }
     }
 }
@@ -1,5 +1,7 @@
 //! Thing Data
 
+use crate::entity::ID;
+
 /// Information specific to things.
 #[derive(Debug, Clone)]
 pub struct ThingComponent {
@@ -8,6 +10,18 @@ pub struct ThingComponent {
 
     /// The thing's noun, for use in commands
     pub noun: String,
+
+    /// Multi-word nouns for the thing, e.g., "brass lantern".  The parser treats
+    /// these as single words.
+    pub phrases: Vec<String>,
+
+    /// The thing's plural name, for display when several things with the
+    /// same name are listed together.  Defaults to the name plus "s".
+    pub plural: Option<String>,
+
+    /// The room the player goes to on entering the thing, e.g., a cave mouth
+    /// or a portal, if any.
+    pub leads_to: Option<ID>,
 }
 
 impl ThingComponent {
@@ -16,6 +30,17 @@ impl ThingComponent {
         ThingComponent {
             name: name.into(),
             noun: noun.into(),
+            phrases: Vec::new(),
+            plural: None,
+            leads_to: None,
+        }
+    }
+
+    /// Returns the thing's plural name.
+    pub fn plural(&self) -> String {
+        match &self.plural {
+            Some(plural) => plural.clone(),
+            None => format!("{}s", self.name),
         }
     }
 }
@@ -0,0 +1,68 @@
+//! Thing View
+//! A ThingView is a checked-out copy of a thing's components.  It can be mutated freely
+//! and then saved back into the world.  A thing's location is not part of the view;
+//! use the phys system to move things about.
+
+use crate::entity::flag_set_component::FlagSetComponent;
+use crate::entity::thing_component::ThingComponent;
+use crate::entity::ID;
+use crate::world::World;
+
+/// A mutable view of a thing entity.
+#[derive(Debug, Clone)]
+pub struct ThingView {
+    /// The thing's ID
+    pub id: ID,
+
+    /// The thing's thing component
+    pub thing: ThingComponent,
+
+    /// The thing's flags
+    pub flags: FlagSetComponent,
+}
+
+impl ThingView {
+    /// Checks out a view of the given thing.
+    ///
+    /// * Panics if the entity is not a thing.
+    pub fn from(world: &World, id: ID) -> Self {
+        assert!(world.is_thing(id), "Not a thing: [{}]", id);
+
+        Self {
+            id,
+            thing: world.things[&id].clone(),
+            flags: world.flag_sets.get(&id).cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Saves the view's components back into the world.
+    pub fn save(self, world: &mut World) {
+        world.things.insert(self.id, self.thing);
+        world.flag_sets.insert(self.id, self.flags);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Flag::User;
+    use crate::world_builder::WorldBuilder;
+
+    #[test]
+    fn from_and_save() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("lamp", "lamp", "lamp").location("here");
+        let mut world = wb.world();
+        let lamp = world.lookup("lamp");
+
+        let mut view = ThingView::from(&world, lamp);
+        view.thing.name = "brass lamp".into();
+        view.flags.set(User("LIT"));
+        view.save(&mut world);
+
+        assert_eq!(world.things[&lamp].name, "brass lamp");
+        assert!(world.has_flag(lamp, User("LIT")));
+    }
+}
@@ -1,7 +1,7 @@
 //! # Bonaventure: A Text Adventure Framework
-/// Bonaventure is a simple text adventure framework.  At present, it is used to
-/// implement a single game; see src/scenario.rs.  Eventually it might support
-/// multiple games.
+/// Bonaventure is a simple text adventure framework.  It can host multiple games,
+/// or "scenarios"; the available scenarios are registered in src/scenario.rs, and
+/// the player can choose one on the command line.
 mod command;
 mod conmark;
 #[macro_use]
@@ -10,6 +10,8 @@ mod debug;
 mod entity;
 mod phys;
 mod player_control;
+#[allow(dead_code)] // Not all scenarios use randomness.
+mod rng;
 mod rule;
 mod scenario;
 mod script;
@@ -20,49 +22,142 @@ mod world;
 mod world_builder;
 
 use crate::types::Event;
+use crate::types::Time;
 use crate::world::*;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::Once;
+
+/// The title to display if the scenario doesn't provide one.
+const DEFAULT_TITLE: &str = "Bonaventure";
+
+thread_local! {
+    /// If true, panics on this thread aren't reported by the panic hook; see
+    /// `Game::turn`.
+    static QUIET_PANICS: Cell<bool> = const { Cell::new(false) };
+}
 
 /// The main game object.  It owns the world as it currently is, and supports restart
 /// and undo, etc.
 /// TODO: Possibly, this should live elsewhere.
 pub struct Game {
+    // The name of the scenario being played
+    scenario: String,
+
     // THe current world
     world: World,
 
     // Undo information
     undo_info: Option<World>,
+
+    // Named checkpoints, for debugging
+    checkpoints: HashMap<String, World>,
+
+    // If true, failed commands are followed by diagnostic information
+    debug: bool,
+
+    // If true, panics during a turn aren't caught.  Defaults to true in debug builds.
+    strict: bool,
+
+    // If true, the player has quit.
+    over: bool,
 }
 
 impl Default for Game {
     fn default() -> Self {
-        Self::new()
+        Self::new(scenario::DEFAULT)
     }
 }
 
 impl Game {
-    /// Create the game object
-    pub fn new() -> Game {
+    /// Create the game object for the named scenario.
+    ///
+    /// * Panics if there is no such scenario.
+    pub fn new(scenario: &str) -> Game {
         Game {
-            world: scenario::build(),
+            scenario: scenario.into(),
+            world: build_scenario(scenario),
             undo_info: None,
+            checkpoints: HashMap::new(),
+            debug: false,
+            strict: cfg!(debug_assertions),
+            over: false,
         }
     }
 
-    /// Introduce the game: print a welcome message, and visualize the initial location
+    /// Introduce the game: print a welcome message and the scenario's intro banner,
+    /// and visualize the initial location
     pub fn introduce(&mut self) {
-        println!("Welcome to Bonaventure!\n");
+        let title = self.world.title.as_ref().map_or(DEFAULT_TITLE, |t| t.as_str());
+        println!("Welcome to {}!\n", title);
+
+        if let Some(intro) = &self.world.intro {
+            visual::info(intro);
+        }
 
         // The first turn is always an implicit "look at the current setting".
         // This will also give everything else a chance to move.
         self.turn("look");
     }
 
-    /// Execute one game turn.
+    /// Execute one game turn.  In release builds, a panic during the turn (e.g., due
+    /// to a malformed scenario) is reported as an internal error, and the world is
+    /// restored to its state before the turn; debug builds panic as usual, so that
+    /// authors see the problem immediately.  While the turn is in progress, panics
+    /// on this thread aren't reported by the panic hook, so that the player sees only
+    /// the report.
     pub fn turn(&mut self, cmd: &str) {
+        if self.strict {
+            self.play_turn(cmd);
+            return;
+        }
+
+        install_panic_hook();
+        let saved = self.world.clone();
+        QUIET_PANICS.with(|q| q.set(true));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.play_turn(cmd)));
+        QUIET_PANICS.with(|q| q.set(false));
+
+        if let Err(payload) = result {
+            self.world = saved;
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown error".into());
+            visual::error(&format!("Internal error: {}", msg));
+        }
+    }
+
+    /// Plays one game turn: handles the player's command, fires the turn's rules,
+    /// and advances the clock.
+    fn play_turn(&mut self, cmd: &str) {
         // FIRST, let the player do what he does.
         player_control::system(self, &cmd);
 
-        // NEXT, handle rules
+        // NEXT, if he quit, nothing else happens.
+        if self.over {
+            return;
+        }
+
+        self.end_turn();
+
+        // NEXT, if he's walking somewhere by steps, take the remaining steps, one per
+        // turn, so that the world can react as he goes.
+        while !self.world.route.is_empty() {
+            if let Err(msg) = player_control::walk_step(&mut self.world) {
+                visual::error(&msg);
+                self.world.route.clear();
+            }
+            self.end_turn();
+        }
+    }
+
+    /// Ends the turn: fires the turn's rules and advances the clock.
+    fn end_turn(&mut self) {
+        // FIRST, handle rules
         rule::fire_event(&mut self.world, &Event::Turn);
 
         // NEXT, Increment the clock
@@ -72,13 +167,36 @@ impl Game {
         self.world.clock += 1;
     }
 
+    /// Returns the data for a status line: the name of the player's current room,
+    /// the score, and the game clock.
+    pub fn status_line(&self) -> (String, u32, Time) {
+        let loc = phys::loc(&self.world, self.world.pid);
+        let room = self
+            .world
+            .rooms
+            .get(&loc)
+            .map_or_else(String::new, |roomc| roomc.name.clone());
+
+        (room, self.world.score, self.world.clock)
+    }
+
     /// Restart the game: recreate the initial scenario.
     pub fn restart(&mut self) {
-        self.world = scenario::build();
+        self.world = build_scenario(&self.scenario);
         self.undo_info = None;
         self.introduce();
     }
 
+    /// Ends the game; the game loop will exit.
+    pub fn quit(&mut self) {
+        self.over = true;
+    }
+
+    /// Has the player quit the game?
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
     /// Saves the world state for later undo.
     pub fn save_for_undo(&mut self, undo_info: World) {
         // At present, we save only one turn.
@@ -94,18 +212,255 @@ impl Game {
         assert!(self.has_undo(), "Cannot undo; no undo info");
         self.world = self.undo_info.take().unwrap();
     }
+
+    /// Reseeds the world's random number generator, e.g., for tests and replays.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.world.rng.reseed(seed);
+    }
+
+    /// Runs the commands in the named file, as though the player had typed them.
+    /// See `replay`.
+    pub fn run_script(&mut self, path: &str) -> std::io::Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        self.replay(&text);
+        Ok(())
+    }
+
+    /// Runs the commands in the text, one per line, echoing each as it is run.
+    /// Blank lines and lines beginning with '#' are ignored.
+    pub fn replay(&mut self, text: &str) {
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            println!("> {}", line);
+            self.turn(line);
+
+            if self.over {
+                break;
+            }
+        }
+    }
+
+    /// Saves a copy of the current world as a named checkpoint, replacing any
+    /// existing checkpoint with that name.  Checkpoints survive restart.
+    pub fn save_checkpoint(&mut self, name: &str) {
+        self.checkpoints.insert(name.into(), self.world.clone());
+    }
+
+    /// Restores the world from the named checkpoint.  Returns false if there
+    /// is no such checkpoint.
+    pub fn load_checkpoint(&mut self, name: &str) -> bool {
+        if let Some(world) = self.checkpoints.get(name) {
+            self.world = world.clone();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Installs a panic hook that is silent on threads playing a lenient turn, and
+/// otherwise defers to the previous hook.  The hook is installed only once.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET_PANICS.with(|q| q.get()) {
+                prev(info);
+            }
+        }));
+    });
+}
+
+/// Builds the named scenario's world, panicking if there is no such scenario.
+fn build_scenario(name: &str) -> World {
+    scenario::build_named(name).unwrap_or_else(|| panic!("Unknown scenario: {}", name))
 }
 
-/// Runs the program.
+/// Runs the program.  The scenario to play may be given as the first command-line
+/// argument; otherwise, the default scenario is played.  If "--replay <file>" is
+/// given, the commands in the file are run non-interactively instead.
 pub fn run() {
-    // FIRST, create the game world.
-    let mut game = Game::new();
+    // FIRST, parse the command line.
+    let mut name = scenario::DEFAULT.to_string();
+    let mut replay: Option<String> = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--replay" {
+            replay = args.next();
+            if replay.is_none() {
+                println!("Missing file name for --replay");
+                ::std::process::exit(1);
+            }
+        } else {
+            name = arg;
+        }
+    }
+
+    if !scenario::list().contains(&name.as_str()) {
+        println!("Unknown scenario: {}", name);
+        println!("Available scenarios: {}", scenario::list().join(", "));
+        ::std::process::exit(1);
+    }
+
+    // NEXT, create the game world.
+    let mut game = Game::new(&name);
     game.introduce();
 
+    // NEXT, replay the script, if any.
+    if let Some(path) = replay {
+        if let Err(err) = game.run_script(&path) {
+            println!("Could not read {}: {}", path, err);
+            ::std::process::exit(1);
+        }
+        return;
+    }
+
     // NEXT, enter the game loop.
     let mut con = console::Console::new();
 
-    loop {
-        game.turn(&con.readline("> "));
+    while !game.is_over() {
+        match con.readline("> ") {
+            console::Input::Line(line) => game.turn(&line),
+            console::Input::Interrupt => {
+                if con.confirm("Really quit? (y/n) ") {
+                    game.turn("quit");
+                }
+            }
+            console::Input::Eof => game.turn("quit"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_line() {
+        let mut game = Game::default();
+        assert_eq!(game.status_line(), ("A Dreary Clearing".into(), 0, 0));
+
+        game.world.score = 5;
+        game.world.clock = 3;
+        assert_eq!(game.status_line(), ("A Dreary Clearing".into(), 5, 3));
+    }
+
+    #[test]
+    fn restart_resets_world() {
+        let mut fresh = Game::default();
+        visual::capture(|| fresh.introduce());
+
+        let mut game = Game::default();
+        visual::capture(|| {
+            game.introduce();
+            for cmd in &["get note", "read note", "south", "wait"] {
+                game.turn(cmd);
+            }
+        });
+        assert!(!game.world.state_eq(&fresh.world));
+
+        visual::capture(|| game.restart());
+        assert!(game.world.state_eq(&fresh.world));
+
+        // The only undo info is for the introductory "look".
+        game.undo();
+        fresh.undo();
+        assert!(game.world.state_eq(&fresh.world));
+    }
+
+    #[test]
+    fn replay_skips_comments() {
+        let mut game = Game::default();
+        let script = "# Get the note\n\nget note\n  # and go\nsouth\n";
+        visual::capture(|| game.replay(script));
+
+        assert_eq!(game.world.clock, 2);
+        assert!(game.world.owns(world_builder::PLAYER, "note"));
+    }
+
+    #[test]
+    fn lenient_turn_reports_panics() {
+        let mut wb = world_builder::WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.verb("boom", &|_, _, _| panic!("Kaboom"));
+
+        let mut game = Game { world: wb.world(), ..Game::default() };
+        game.strict = false;
+
+        let events = visual::capture(|| game.turn("boom"));
+        assert_eq!(events, vec![visual::OutputEvent::Error("Internal error: Kaboom".into())]);
+        assert_eq!(game.world.clock, 0);
+    }
+
+    #[test]
+    fn go_to_by_steps() {
+        for (by_steps, turns) in &[(false, 1), (true, 2)] {
+            let mut game = Game::default();
+            game.world.walk_by_steps = *by_steps;
+            visual::capture(|| {
+                game.introduce();
+                game.turn("south");
+                game.turn("south");
+            });
+            let clock = game.world.clock;
+
+            visual::capture(|| game.turn("go to clearing"));
+            assert_eq!(game.status_line().0, "A Dreary Clearing");
+            assert_eq!(game.world.clock - clock, *turns);
+            assert!(game.world.route.is_empty());
+        }
+    }
+
+    #[test]
+    fn rules_recover_from_panics() {
+        let mut wb = world_builder::WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("gremlin", "gremlin", "gremlin")
+            .location("here")
+            .rule(&world_builder::WBEvent::Turn)
+            .priority(1)
+            .when(&|w| {
+                if w.has(world_builder::PLAYER, types::Flag::User("BOOM")) {
+                    panic!("Kaboom");
+                }
+                false
+            });
+        wb.rule("tick").add_score_quietly(1);
+
+        let mut game = Game { world: wb.world(), ..Game::default() };
+        game.strict = false;
+        let pid = game.world.pid;
+
+        // More panics than the maximum event depth.
+        game.world.set_flag(pid, types::Flag::User("BOOM"));
+        for _ in 0..40 {
+            visual::capture(|| game.turn("look"));
+        }
+        assert_eq!(game.world.score, 0);
+        assert_eq!(rule::owner(), None);
+
+        // Rules still fire.
+        game.world.unset_flag(pid, types::Flag::User("BOOM"));
+        visual::capture(|| game.turn("look"));
+        assert_eq!(game.world.score, 1);
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut game = Game::default();
+        assert!(!game.load_checkpoint("start"));
+
+        game.save_checkpoint("start");
+        game.world.clock = 10;
+        assert!(game.load_checkpoint("start"));
+        assert_eq!(game.world.clock, 0);
     }
 }
@@ -9,11 +9,15 @@ use crate::rule;
 use crate::types::Dir;
 use crate::types::Event::*;
 use crate::types::LinkDest;
+use crate::types::ListOrder;
+use crate::types::Manipulation;
 use crate::types::Flag::*;
 use crate::visual;
 use crate::world::World;
 use crate::world::LIMBO;
 use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::VecDeque;
 
 type PhysResult = Result<(), String>;
 
@@ -39,6 +43,63 @@ pub fn follow_link(world: &World, loc: ID, dir: Dir) -> Option<LinkDest> {
     roomc.links.get(&dir).cloned()
 }
 
+/// Finds the shortest path from one room to another, following links through rooms
+/// the player has already seen.  Returns the directions to follow, or None if there
+/// is no known path.
+pub fn path(world: &World, from: ID, to: ID) -> Option<Vec<Dir>> {
+    assert_is_room(world, from);
+    assert_is_room(world, to);
+
+    // FIRST, do a breadth-first search, remembering how we got to each room.
+    let mut came_from: HashMap<ID, (ID, Dir)> = HashMap::new();
+    let mut queue: VecDeque<ID> = VecDeque::new();
+    queue.push_back(from);
+
+    while let Some(room) = queue.pop_front() {
+        if room == to {
+            break;
+        }
+
+        // Sort the links so that the result is deterministic.
+        let mut links: Vec<(Dir, ID)> = world.rooms[&room]
+            .links
+            .iter()
+            .filter_map(|(dir, dest)| match dest {
+                LinkDest::Room(dest) => Some((*dir, *dest)),
+                _ => None,
+            })
+            .collect();
+        links.sort();
+
+        for (dir, dest) in links {
+            if dest != from
+                && !came_from.contains_key(&dest)
+                && world.has_flag(world.pid, Seen(dest))
+            {
+                came_from.insert(dest, (room, dir));
+                queue.push_back(dest);
+            }
+        }
+    }
+
+    // NEXT, retrace the path, if we found one.
+    if from != to && !came_from.contains_key(&to) {
+        return None;
+    }
+
+    let mut dirs: Vec<Dir> = Vec::new();
+    let mut room = to;
+
+    while room != from {
+        let (prev, dir) = came_from[&room];
+        dirs.push(dir);
+        room = prev;
+    }
+
+    dirs.reverse();
+    Some(dirs)
+}
+
 /// Determines whether the thing is in the container.
 ///
 /// * Panics if the container has no inventory component.
@@ -89,22 +150,24 @@ pub fn non_scenery(world: &World, owner: ID) -> BTreeSet<ID> {
     result
 }
 
-pub fn immovable(world: &World, owner: ID) -> BTreeSet<ID> {
+/// Returns the IDs and display names of the things that should appear when the
+/// owner's contents are listed, i.e., all non-scenery things, in the world's
+/// listing order.
+pub fn named_contents(world: &World, owner: ID) -> Vec<(ID, String)> {
     assert_has_inventory(world, owner);
 
-    let mut result: BTreeSet<ID> = BTreeSet::new();
+    let mut result: Vec<(ID, String)> = non_scenery(world, owner)
+        .into_iter()
+        .filter_map(|id| world.things.get(&id).map(|thingc| (id, thingc.name.clone())))
+        .collect();
 
-    // FIRST, get everything that's flagged as immovable.
-    for id in contents(world, owner) {
-        if world.has_flag(id, Immovable) {
-            result.insert(id);
-        }
+    if world.list_order == ListOrder::Alphabetical {
+        result.sort_by(|a, b| a.1.cmp(&b.1));
     }
 
     result
 }
 
-
 /// Finds all things in the viewer's location that are visible to
 /// the viewer.  This includes things owned by the viewer, present
 /// in the viewer's location, or (ultimately) visible in open containers.
@@ -124,6 +187,20 @@ pub fn visible(world: &World, viewer: ID) -> BTreeSet<ID> {
     result
 }
 
+/// Is the room lit?  I.e., is it not Dark, or is there a light source in the room
+/// or carried by something in the room?
+pub fn is_lit(world: &World, room: ID) -> bool {
+    if !world.has_flag(room, Dark) {
+        return true;
+    }
+
+    contents(world, room).into_iter().any(|id| {
+        world.has_flag(id, LightSource)
+            || (world.has_inventory(id)
+                && contents(world, id).into_iter().any(|t| world.has_flag(t, LightSource)))
+    })
+}
+
 /// Finds all things in the location's inventory that can be removed,
 /// i.e., that isn't flagged as Immovable.
 pub fn removable(world: &World, loc: ID) -> BTreeSet<ID> {
@@ -143,29 +220,13 @@ pub fn removable(world: &World, loc: ID) -> BTreeSet<ID> {
 }
 
 /// Finds all things in the viewer's inventory that he could, in theory,
-/// drop into his location
+/// drop into his location, i.e., that are neither Immovable nor Bound.
 pub fn droppable(world: &World, viewer: ID) -> BTreeSet<ID> {
     assert_has_inventory(world, viewer);
     removable(world, viewer)
-}
-
-/// Finds all things in the viewer's location that he could, in theory,
-/// move to his own inventory, i.e., all things that aren't flagged
-/// as immovable.
-pub fn gettable(world: &World, viewer: ID) -> BTreeSet<ID> {
-    assert_has_location(world, viewer);
-
-    let mut result: BTreeSet<ID> = BTreeSet::new();
-
-    // FIRST, get everything in the current location that isn't
-    // flagged as Immovable.
-    for id in contents(world, loc(world, viewer)) {
-        if !world.has_flag(id, Immovable) {
-            result.insert(id);
-        }
-    }
-
-    result
+        .into_iter()
+        .filter(|id| !world.has_flag(*id, Bound))
+        .collect()
 }
 
 //--------------------------------------------------------------------------------
@@ -200,11 +261,25 @@ pub fn put_in(world: &mut World, thing: ID, container: ID) {
 //---------------------------------------------------------------------------------
 // High-level operations
 
-/// The player tries to enter the room.
+/// The player tries to leave his current location and enter the room.  The
+/// LeaveRoom guard on the current location is checked first, and then the
+/// EnterRoom guard on the destination.  Once the player has entered, each
+/// NPC in the room gets an NpcSees event.
 pub fn enter_room(world: &mut World, pid: ID, room: ID) -> PhysResult {
+    let here = loc(world, pid);
+
+    if !rule::allows(world, &LeaveRoom(pid, here)) {
+        return Ok(());
+    }
+
     if rule::allows(world, &EnterRoom(pid, room)) {
+        rule::fire_event(world, &LeaveRoom(pid, here));
         put_in(world, pid, room);
 
+        if let Some(playerc) = world.players.get_mut(&pid) {
+            playerc.prev_loc = Some(here);
+        }
+
         if !world.has_flag(pid, Seen(room)) {
             visual::room(world, room);
         } else {
@@ -214,13 +289,36 @@ pub fn enter_room(world: &mut World, pid: ID, room: ID) -> PhysResult {
         world.set_flag(pid, Seen(room));
 
         rule::fire_event(world, &EnterRoom(pid, room));
+
+        // Give the NPCs in the room a chance to react.
+        let npcs: Vec<ID> = contents(world, room)
+            .into_iter()
+            .filter(|id| *id != pid && world.has_flags(*id) && world.has_flag(*id, Npc))
+            .collect();
+        for npc in npcs {
+            rule::fire_event(world, &NpcSees(npc, pid));
+        }
     }
 
     Ok(())
 }
 
-/// The player gets the thing.
+/// The player gets the thing.  This is the single path by which things are
+/// taken, so that guards and rules apply consistently.
+///
+/// Immovable things can't be taken.  Immovable scenery is part of its location,
+/// e.g., a pool of water; other immovable things, e.g., a boulder, are listed with
+/// the room's contents but won't budge.  Scenery that isn't immovable can be taken,
+/// e.g., a sword that appears as part of a stone until it's pulled out.
 pub fn get_thing(world: &mut World, pid: ID, thing: ID) -> PhysResult {
+    if world.has_flag(thing, Immovable) {
+        if world.has_flag(thing, Scenery) {
+            return Err("You can't take that!".into());
+        } else {
+            return Err("It won't budge.".into());
+        }
+    }
+
     if rule::allows(world, &GetThing(pid, thing)) {
         put_in(world, thing, pid);
         visual::act("Taken.");
@@ -230,6 +328,70 @@ pub fn get_thing(world: &mut World, pid: ID, thing: ID) -> PhysResult {
     Ok(())
 }
 
+/// The player drops the thing into his current location.
+pub fn drop_thing(world: &mut World, pid: ID, thing: ID) -> PhysResult {
+    if rule::allows(world, &DropThing(pid, thing)) {
+        let here = loc(world, pid);
+        put_in(world, thing, here);
+        visual::act("Dropped.");
+        rule::fire_event(world, &DropThing(pid, thing));
+    }
+
+    Ok(())
+}
+
+/// The player takes the thing out of the container and puts it in his inventory.
+pub fn take_from(world: &mut World, pid: ID, thing: ID, container: ID) -> PhysResult {
+    if world.has_flag(thing, Immovable) {
+        return Err("It won't budge.".into());
+    }
+
+    if rule::allows(world, &TakeFrom(pid, thing, container)) {
+        put_in(world, thing, pid);
+        visual::act("Taken.");
+        rule::fire_event(world, &TakeFrom(pid, thing, container));
+    }
+
+    Ok(())
+}
+
+/// The player gives the thing to the recipient, which must have an inventory.
+pub fn give_thing(world: &mut World, pid: ID, thing: ID, recipient: ID) -> PhysResult {
+    assert_has_inventory(world, recipient);
+
+    if rule::allows(world, &GiveThing(pid, thing, recipient)) {
+        put_in(world, thing, recipient);
+        visual::act("Given.");
+        rule::fire_event(world, &GiveThing(pid, thing, recipient));
+    }
+
+    Ok(())
+}
+
+/// The player uses the tool on the target.  What happens is up to the scenario's
+/// rules; if none fire, nothing happens.
+pub fn use_on(world: &mut World, pid: ID, tool: ID, target: ID) -> PhysResult {
+    if rule::allows(world, &UseOn(pid, tool, target))
+        && !rule::fire_event(world, &UseOn(pid, tool, target))
+    {
+        visual::act("Nothing happens.");
+    }
+
+    Ok(())
+}
+
+/// The player pushes, pulls, etc., the thing.  What happens is up to the scenario's
+/// rules; if none fire, the player gets the manipulation's default response.
+pub fn manipulate(world: &mut World, pid: ID, thing: ID, how: Manipulation) -> PhysResult {
+    if rule::allows(world, &Manipulate(pid, thing, how))
+        && !rule::fire_event(world, &Manipulate(pid, thing, how))
+    {
+        visual::act(how.default_response());
+    }
+
+    Ok(())
+}
+
 /// The player reads the thing's Book prose.
 pub fn read_thing(world: &mut World, pid: ID, thing: ID) -> PhysResult {
     if rule::allows(world, &ReadThing(pid, thing)) {
@@ -266,3 +428,208 @@ fn assert_has_location(world: &World, thing: ID) {
         idtag(world, thing)
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario;
+    use crate::world::WorldQuery;
+    use crate::world_builder::*;
+
+    #[test]
+    fn is_lit_by_light_source() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("cave");
+        wb.room("cave", "Cave").flag(Dark);
+        wb.room("field", "Field");
+        wb.thing("lamp", "lamp", "lamp").location("field").flag(LightSource);
+        let mut world = wb.world();
+        let cave = world.lookup("cave");
+        let lamp = world.lookup("lamp");
+
+        assert!(is_lit(&world, world.lookup("field")));
+        assert!(!is_lit(&world, cave));
+
+        let pid = world.pid;
+        put_in(&mut world, lamp, pid);
+        assert!(is_lit(&world, cave));
+    }
+
+    #[test]
+    fn named_contents_order() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("zebra", "zebra", "zebra").location("here");
+        wb.thing("apple", "apple", "apple").location("here");
+        let mut world = wb.world();
+        let here = world.lookup("here");
+
+        let names: Vec<String> = named_contents(&world, here).into_iter().map(|(_, n)| n).collect();
+        assert_eq!(names, vec!["zebra", "apple"]);
+
+        world.list_order = ListOrder::Alphabetical;
+        let names: Vec<String> = named_contents(&world, here).into_iter().map(|(_, n)| n).collect();
+        assert_eq!(names, vec!["apple", "zebra"]);
+    }
+
+    #[test]
+    fn get_thing_rejects_immovable() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.feature("statue", "statue", "statue").location("here");
+        wb.thing("boulder", "boulder", "boulder").location("here").flag(Immovable);
+        let mut world = wb.world();
+        let pid = world.pid;
+        let here = world.lookup("here");
+        let statue = world.lookup("statue");
+        let boulder = world.lookup("boulder");
+
+        // Scenery isn't listed, and can't be taken.
+        let names: Vec<String> = named_contents(&world, here).into_iter().map(|(_, n)| n).collect();
+        assert_eq!(names, vec!["boulder"]);
+        assert_eq!(get_thing(&mut world, pid, statue), Err("You can't take that!".into()));
+        assert_eq!(loc(&world, statue), here);
+
+        // An immovable thing is listed, but can't be taken.
+        assert_eq!(get_thing(&mut world, pid, boulder), Err("It won't budge.".into()));
+        assert_eq!(loc(&world, boulder), here);
+    }
+
+    #[test]
+    fn droppable_excludes_bound() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("coin", "coin", "coin").location(PLAYER);
+        wb.thing("sword", "sword", "sword").location(PLAYER).flag(Bound);
+        let world = wb.world();
+
+        let names: Vec<ID> = droppable(&world, world.pid).into_iter().collect();
+        assert_eq!(names, vec![world.lookup("coin")]);
+    }
+
+    #[test]
+    fn path_through_seen_rooms() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("a");
+        wb.room("a", "A").link_both(Dir::East, "b").link_both(Dir::South, "c");
+        wb.room("b", "B").link_both(Dir::South, "d");
+        wb.room("c", "C").link_both(Dir::East, "d");
+        wb.room("d", "D");
+        let mut world = wb.world();
+        let pid = world.pid;
+        let a = world.lookup("a");
+        let b = world.lookup("b");
+        let c = world.lookup("c");
+        let d = world.lookup("d");
+
+        // Unseen rooms aren't known.
+        assert_eq!(path(&world, a, d), None);
+
+        world.set_flag(pid, Seen(b));
+        world.set_flag(pid, Seen(d));
+        assert_eq!(path(&world, a, d), Some(vec![Dir::East, Dir::South]));
+
+        // When there's a choice, links are followed in direction order.
+        world.set_flag(pid, Seen(c));
+        assert_eq!(path(&world, a, d), Some(vec![Dir::South, Dir::East]));
+        assert_eq!(path(&world, a, a), Some(vec![]));
+    }
+
+    #[test]
+    fn give_thing_to_recipient() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("troll", "troll", "troll").inventory().location("here");
+        wb.thing("coin", "coin", "coin").location(PLAYER);
+        wb.thing("rock", "rock", "rock").location(PLAYER);
+        wb.allow(&WBEvent::GiveThing("rock", "troll"))
+            .print("The troll doesn't want a rock.");
+        let mut world = wb.world();
+        let pid = world.pid;
+        let troll = world.lookup("troll");
+        let coin = world.lookup("coin");
+        let rock = world.lookup("rock");
+
+        give_thing(&mut world, pid, coin, troll).unwrap();
+        assert!(owns(&world, troll, coin));
+
+        give_thing(&mut world, pid, rock, troll).unwrap();
+        assert!(owns(&world, pid, rock));
+    }
+
+    #[test]
+    fn enter_room_denied_by_guard() {
+        let mut world = scenario::build();
+        let pid = world.pid;
+        let mouth = world.lookup("cave-mouth");
+        let cave = world.lookup("cave-1");
+        put_in(&mut world, pid, mouth);
+
+        // The player can't enter the cave without the sword.
+        enter_room(&mut world, pid, cave).unwrap();
+        assert_eq!(loc(&world, pid), mouth);
+        assert!(!world.has("on-enter-cave-1", Fired));
+    }
+
+    #[test]
+    fn enter_room_fires_rules() {
+        let mut world = scenario::build();
+        let pid = world.pid;
+        let mouth = world.lookup("cave-mouth");
+        let cave = world.lookup("cave-1");
+        let sword = world.lookup("sword");
+        put_in(&mut world, pid, mouth);
+        put_in(&mut world, sword, pid);
+
+        enter_room(&mut world, pid, cave).unwrap();
+        assert_eq!(loc(&world, pid), cave);
+        assert!(world.has_flag(pid, Seen(cave)));
+        assert!(world.has("on-enter-cave-1", Fired));
+        assert_eq!(world.players[&pid].prev_loc, Some(mouth));
+    }
+
+    #[test]
+    fn enter_room_npc_sees() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.room("there", "There").prose("A troll's lair.");
+        wb.thing("troll", "troll", "troll")
+            .location("there")
+            .npc()
+            .rule(&WBEvent::NpcSees("troll"))
+            .set_flag("troll", User("ANGRY"));
+        let mut world = wb.world();
+        let pid = world.pid;
+        let there = world.lookup("there");
+
+        enter_room(&mut world, pid, there).unwrap();
+        assert!(world.has("troll", User("ANGRY")));
+    }
+
+    #[test]
+    fn enter_room_only_npcs_see() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.room("there", "There");
+        wb.thing("statue", "statue", "statue")
+            .location("there")
+            .npc()
+            .rule(&WBEvent::NpcSees("statue"))
+            .set_flag("statue", User("WATCHING"));
+        let mut world = wb.world();
+        let pid = world.pid;
+        let statue = world.lookup("statue");
+        let there = world.lookup("there");
+
+        // Once it's just a thing, it doesn't see the player.
+        world.unset_flag(statue, Npc);
+        visual::capture(|| enter_room(&mut world, pid, there).unwrap());
+        assert!(!world.has("statue", User("WATCHING")));
+    }
+}
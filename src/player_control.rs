@@ -7,7 +7,7 @@ use crate::command::Command;
 use crate::debug;
 use crate::entity::ID;
 use crate::phys;
-use crate::types::Dir::*;
+use crate::rule;
 use crate::types::Flag::*;
 use crate::types::*;
 use crate::visual;
@@ -26,6 +26,9 @@ enum Status {
 
     /// Undo the last command (plus anything that happened after, e.g., rule firings)
     Undo,
+
+    /// Quit response; the game is over, and the game loop should end.
+    Quit,
 }
 
 /// A status result, used for special commands
@@ -49,14 +52,23 @@ pub fn system(game: &mut Game, input: &str) {
     };
 
     // NEXT, handle the input
+    rule::take_denial();
     let result = handle_input(game, &player, input);
-    match result {
-        Err(msg) => visual::error(&msg),
+    let denial = rule::take_denial();
+
+    match &result {
+        Err(msg) => visual::error(msg),
         Ok(Normal) => {
             game.save_for_undo(undo_info);
         }
         Ok(Restart) => game.restart(),
         Ok(Undo) => game.undo(),
+        Ok(Quit) => game.quit(),
+    }
+
+    // NEXT, if debugging, explain any failure.
+    if game.debug && (result.is_err() || denial.is_some()) {
+        print_diagnostics(&game.world, &player, input, denial);
     }
 }
 
@@ -71,12 +83,61 @@ fn handle_input(game: &mut Game, player: &Player, input: &str) -> StatusResult {
     }
 }
 
+/// Prints diagnostic information about a failed or denied command, for debugging:
+/// how it was parsed and handled, the entities its words refer to, and the guard
+/// that denied it, if any.
+fn print_diagnostics(world: &World, player: &Player, input: &str, denial: Option<ID>) {
+    let cmd = match command::parse(world, input) {
+        Ok(cmd) => cmd,
+        Err(msg) => {
+            visual::info(&format!("[debug] Parse error: {}", msg));
+            return;
+        }
+    };
+
+    let words: Vec<&str> = cmd.words.iter().map(|s| s.as_ref()).collect();
+    visual::info(&format!("[debug] Words: {:?}", words));
+
+    // FIRST, how was it handled?
+    let handler = if cmd.is_debug {
+        "debugging command"
+    } else if world.command_handlers.iter().any(|h| h.matches(&words)) {
+        "custom command"
+    } else if words.first().is_some_and(|w| world.verbs.contains(*w)) {
+        "built-in command"
+    } else {
+        "unknown verb"
+    };
+    visual::info(&format!("[debug] Handler: {}", handler));
+
+    // NEXT, what entities do the words refer to?
+    let visible = phys::visible(world, player.id);
+    for word in words.iter().skip(1) {
+        if let Some(id) = find_noun(world, visible.clone(), word) {
+            visual::info(&format!("[debug] {} = [{}] {}", word, id, world.tag(id)));
+        }
+    }
+
+    // NEXT, was it denied by a guard?
+    if let Some(id) = denial {
+        visual::info(&format!("[debug] Denied by: [{}] {}", id, world.tag(id)));
+    }
+}
+
 fn handle_normal_command(game: &mut Game, player: &Player, cmd: &Command) -> StatusResult {
     let world = &mut game.world;
     let the_words: Vec<&str> = cmd.words.iter().map(|s| s.as_ref()).collect();
     let words = the_words.as_slice();
 
-    // FIRST, handle custom commands.
+    // FIRST, a dead player can't do much of anything.
+    if world.has_flag(player.id, Dead) {
+        match words {
+            ["undo"] | ["restart"] | ["quit"] => (),
+            _ => return Err("You can't do that; you're dead.".into()),
+        }
+    }
+
+    // NEXT, handle custom commands.
     // TODO: Possible better way to handle this: write function that matches the words
     // and clones the specific handler.
     for handler in world.command_handlers.clone() {
@@ -88,22 +149,58 @@ fn handle_normal_command(game: &mut Game, player: &Player, cmd: &Command) -> Sta
 
     // NEXT, handle built-in commands
     match words {
-        ["go", "north"] => cmd_go(world, player, North),
-        ["north"] => cmd_go(world, player, North),
-        ["go", "south"] => cmd_go(world, player, South),
-        ["south"] => cmd_go(world, player, South),
-        ["go", "east"] => cmd_go(world, player, East),
-        ["east"] => cmd_go(world, player, East),
-        ["go", "west"] => cmd_go(world, player, West),
-        ["west"] => cmd_go(world, player, West),
+        ["back"] => cmd_back(world, player),
+        ["go", "back"] => cmd_back(world, player),
+        ["go", "to", name @ ..] if !name.is_empty() => cmd_go_to(world, player, name),
+        ["enter"] => cmd_go(world, player, Dir::In),
+        ["enter", name] => cmd_enter(world, player, name),
+        ["climb"] | ["climb", "up"] => cmd_climb(world, player, Dir::Up),
+        ["climb", "down"] => cmd_climb(world, player, Dir::Down),
+        ["climb", name] => cmd_climb_thing(world, player, name),
+        ["go", word] | [word] if Dir::from_str(word).is_some() => {
+            cmd_go(world, player, Dir::from_str(word).unwrap())
+        }
+        [word] if world.easter_eggs.contains_key(*word) => cmd_easter_egg(world, word),
         ["help"] => cmd_help(),
+        ["hint"] => cmd_hint(world),
         ["look"] => cmd_look(world, player),
+        ["exits"] => cmd_exits(world, player),
         ["inventory"] => cmd_inventory(world, player),
         ["examine", name] => cmd_examine(world, player, name),
+        ["examine", name, "in", container] => cmd_examine_in(world, player, name, container),
+        ["look", "at", name, "in", container] => {
+            cmd_examine_in(world, player, name, container)
+        }
         ["read", name] => cmd_read(world, player, name),
+        ["smell"] => cmd_sense(world, player.loc, ProseType::Smell),
+        ["smell", name] => cmd_sense_thing(world, player, name, ProseType::Smell),
+        ["listen"] => cmd_sense(world, player.loc, ProseType::Sound),
+        ["listen", "to", name] => cmd_sense_thing(world, player, name, ProseType::Sound),
+        ["touch", name] => cmd_sense_thing(world, player, name, ProseType::Touch),
+        ["open", name] => cmd_open(world, player, name),
+        ["close", name] => cmd_close(world, player, name),
         ["get", name] => cmd_get(world, player, name),
+        ["get", name, "from", container] => cmd_take_from(world, player, name, container),
+        ["get", name, "out", "of", container] => {
+            cmd_take_from(world, player, name, container)
+        }
         ["pick", "up", name] => cmd_get(world, player, name),
         ["drop", name] => cmd_drop(world, player, name),
+        ["give", name, "to", recipient] => cmd_give(world, player, name, recipient),
+        ["use", tool, "on", target] => cmd_use_on(world, player, tool, target),
+        ["use", tool, "with", target] => cmd_use_on(world, player, tool, target),
+        [verb, name] if Manipulation::from_verb(verb).is_some() => {
+            cmd_manipulate(world, player, name, Manipulation::from_verb(verb).unwrap())
+        }
+        ["score"] => cmd_score(world),
+        ["notify"] => cmd_notify(world, !world.notify),
+        ["notify", "on"] => cmd_notify(world, true),
+        ["notify", "off"] => cmd_notify(world, false),
+        ["alias"] => cmd_list_aliases(world),
+        ["alias", name, expansion @ ..] if !expansion.is_empty() => {
+            cmd_alias(world, name, expansion)
+        }
+        ["unalias", name] => cmd_unalias(world, name),
         ["undo"] => cmd_undo(game),
         ["restart"] => cmd_restart(),
         ["quit"] => cmd_quit(),
@@ -120,7 +217,7 @@ fn handle_normal_command(game: &mut Game, player: &Player, cmd: &Command) -> Sta
 fn cmd_help() -> StatusResult {
     visual::info(
         "\
-You've got the usual commands: n, s, e, w, look, get, drop, quit.
+You've got the usual commands: n, s, e, w, look, get, drop, hint, quit.
 You know.  Like that.
     ",
     );
@@ -128,6 +225,26 @@ You know.  Like that.
     Ok(Normal)
 }
 
+/// Respond to a flavor verb, e.g., "xyzzy".
+fn cmd_easter_egg(world: &World, word: &str) -> StatusResult {
+    visual::info(&world.easter_eggs[word]);
+    Ok(Normal)
+}
+
+/// Display the next hint relevant to the player's current situation.
+fn cmd_hint(world: &mut World) -> StatusResult {
+    // Hint predicates query the world, so find the hint first.
+    let index = (0..world.hints.len()).find(|i| (world.hints[*i].predicate)(world));
+
+    if let Some(i) = index {
+        let text = world.hints[i].next();
+        visual::info(&text);
+        Ok(Normal)
+    } else {
+        Err("You're doing fine; you don't need a hint right now.".into())
+    }
+}
+
 /// Move the player in the given direction
 fn cmd_go(world: &mut World, player: &Player, dir: Dir) -> StatusResult {
     match phys::follow_link(world, player.loc, dir) {
@@ -135,13 +252,144 @@ fn cmd_go(world: &mut World, player: &Player, dir: Dir) -> StatusResult {
             phys::enter_room(world, player.id, dest)?;
             Ok(Normal)
         },
-        Some(LinkDest::DeadEnd(prose)) => {
+        Some(LinkDest::DeadEnd(prose, _)) => {
             visual::info(&prose);
             Ok(Normal)
         }
-        None => {
-            Err("You can't go that way.".into())
+        None => match world.rooms[&player.loc].no_exits.get(&dir) {
+            Some(msg) => Err(msg.clone()),
+            None => Err("You can't go that way.".into()),
+        },
+    }
+}
+
+/// Move the player through a thing that leads somewhere, e.g., a portal.
+fn cmd_enter(world: &mut World, player: &Player, name: &str) -> StatusResult {
+    if let Some(thing) = find_noun(world, phys::visible(world, player.id), name) {
+        if let Some(dest) = world.things[&thing].leads_to {
+            phys::enter_room(world, player.id, dest)?;
+            Ok(Normal)
+        } else {
+            Err("You can't enter that.".into())
         }
+    } else {
+        Err("You don't see any such thing.".into())
+    }
+}
+
+/// Climb up or down, if there's a vertical link.
+fn cmd_climb(world: &mut World, player: &Player, dir: Dir) -> StatusResult {
+    if phys::follow_link(world, player.loc, dir).is_none() {
+        return Err("You can't climb that.".into());
+    }
+
+    cmd_go(world, player, dir)
+}
+
+/// Climb a thing, e.g., a ladder: go where it leads, if anywhere, or else up.
+fn cmd_climb_thing(world: &mut World, player: &Player, name: &str) -> StatusResult {
+    let thing = find_noun(world, phys::visible(world, player.id), name)
+        .ok_or_else(|| "You don't see any such thing.".to_string())?;
+
+    if world.things[&thing].leads_to.is_some() {
+        cmd_enter(world, player, name)
+    } else {
+        cmd_climb(world, player, Dir::Up)
+    }
+}
+
+/// Walk the player to the named room, provided that he knows the way, i.e., there's
+/// a path through rooms he's already seen.  The room can be given by name or tag.
+/// If the world walks by steps, the player takes the first step now and the rest
+/// on the following turns; see `walk_step`.  Otherwise, he walks all the way now.
+fn cmd_go_to(world: &mut World, player: &Player, name: &[&str]) -> StatusResult {
+    let name = name.join(" ");
+
+    let dest = world
+        .rooms
+        .iter()
+        .find(|(id, roomc)| world.tag(**id) == name || room_words(&roomc.name) == name)
+        .map(|(id, _)| *id);
+
+    let dirs = match dest {
+        Some(dest) if world.has_flag(player.id, Seen(dest)) => phys::path(world, player.loc, dest),
+        _ => None,
+    };
+
+    let dirs = dirs.ok_or_else(|| "You don't know how to get there.".to_string())?;
+
+    if dirs.is_empty() {
+        return Err("You're already there.".into());
+    }
+
+    if world.walk_by_steps {
+        world.route = dirs.into();
+        walk_step(world)?;
+    } else {
+        // Walk one step at a time, stopping if a guard stops the player.
+        for dir in dirs {
+            if !take_step(world, player.id, dir)? {
+                break;
+            }
+        }
+    }
+
+    Ok(Normal)
+}
+
+/// Takes the next step on the player's "go to" route, if any.  The rest of the route
+/// is abandoned if the step doesn't get him there safely.
+pub fn walk_step(world: &mut World) -> Result<(), String> {
+    if let Some(dir) = world.route.pop_front() {
+        if !take_step(world, world.pid, dir)? {
+            world.route.clear();
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves the player one step in the given direction.  Returns true if he arrived,
+/// and false if, e.g., a guard stopped him.
+fn take_step(world: &mut World, pid: ID, dir: Dir) -> Result<bool, String> {
+    let here = phys::loc(world, pid);
+    if let Some(LinkDest::Room(next)) = phys::follow_link(world, here, dir) {
+        phys::enter_room(world, pid, next)?;
+        Ok(phys::loc(world, pid) == next && !world.has_flag(pid, Dead))
+    } else {
+        Ok(false)
+    }
+}
+
+/// Converts a room name into the words the command parser would produce for it:
+/// lower case, without articles.
+fn room_words(name: &str) -> String {
+    name.to_lowercase()
+        .split_whitespace()
+        .filter(|word| !matches!(*word, "a" | "an" | "the"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Move the player back to the room he was in previously, if there's a link
+/// leading there.
+fn cmd_back(world: &mut World, player: &Player) -> StatusResult {
+    let prev = world.players[&player.id].prev_loc;
+
+    if let Some(prev) = prev {
+        let dir = world.rooms[&player.loc]
+            .links
+            .iter()
+            .find(|(_, dest)| matches!(dest, LinkDest::Room(id) if *id == prev))
+            .map(|(dir, _)| *dir);
+
+        if let Some(dir) = dir {
+            return cmd_go(world, player, dir);
+        }
+
+        Err("You can't go back that way.".into())
+    } else {
+        Err("You haven't been anywhere else yet.".into())
     }
 }
 
@@ -151,6 +399,12 @@ fn cmd_look(world: &World, player: &Player) -> StatusResult {
     Ok(Normal)
 }
 
+/// List the obvious exits from the current location.
+fn cmd_exits(world: &World, player: &Player) -> StatusResult {
+    visual::exits(world, player.loc);
+    Ok(Normal)
+}
+
 /// Display the player's inventory.
 fn cmd_inventory(world: &World, player: &Player) -> StatusResult {
     visual::player_inventory(world, player.id);
@@ -158,12 +412,16 @@ fn cmd_inventory(world: &World, player: &Player) -> StatusResult {
 }
 
 /// Describe a thing in the current location.
-fn cmd_examine(world: &World, player: &Player, name: &str) -> StatusResult {
+fn cmd_examine(world: &mut World, player: &Player, name: &str) -> StatusResult {
     if let Some(thing) = find_noun(world, phys::visible(world, player.id), name) {
         if thing == player.id {
             visual::player(world, player.id);
         } else {
+            if !phys::is_lit(world, player.loc) {
+                return Err("It's too dark to see that.".into());
+            }
             visual::thing(world, thing);
+            world.set_flag(thing, Handled);
         }
         Ok(Normal)
     } else {
@@ -171,6 +429,38 @@ fn cmd_examine(world: &World, player: &Player, name: &str) -> StatusResult {
     }
 }
 
+/// Describe a thing in a visible container, e.g., "examine coin in chest".
+fn cmd_examine_in(
+    world: &mut World,
+    player: &Player,
+    name: &str,
+    container: &str,
+) -> StatusResult {
+    // FIRST, find the container.
+    let cid = find_noun(world, phys::visible(world, player.id), container)
+        .ok_or_else(|| "You don't see any such thing.".to_string())?;
+
+    if cid == player.id || !world.has_inventory(cid) {
+        return Err("There's nothing in that.".into());
+    }
+
+    if world.has_flag(cid, Closed) {
+        return Err(format!("The {} is closed.", world.things[&cid].noun));
+    }
+
+    // NEXT, find the thing within it.
+    if let Some(thing) = find_noun(world, phys::contents(world, cid), name) {
+        if !phys::is_lit(world, player.loc) {
+            return Err("It's too dark to see that.".into());
+        }
+        visual::thing(world, thing);
+        world.set_flag(thing, Handled);
+        Ok(Normal)
+    } else {
+        Err(format!("You don't see any such thing in the {}.", world.things[&cid].noun))
+    }
+}
+
 /// Read a thing in the current location.
 fn cmd_read(world: &mut World, player: &Player, name: &str) -> StatusResult {
     if let Some(thing) = find_noun(world, phys::visible(world, player.id), name) {
@@ -179,6 +469,16 @@ fn cmd_read(world: &mut World, player: &Player, name: &str) -> StatusResult {
             return Err("You can't read that.".into());
         }
 
+        // He can open a closed book to read it, but not a locked one.
+        if world.has_flag(thing, Locked) {
+            return Err("It's locked.".into());
+        }
+
+        // He can't read in the dark.
+        if !phys::is_lit(world, player.loc) {
+            return Err("It's too dark to read.".into());
+        }
+
         // If he's holding it, or it's immovable, then he can read it.
         if phys::owns(world, player.id, thing) || world.has_flag(thing, Immovable) {
             phys::read_thing(world, player.id, thing)?;
@@ -192,6 +492,65 @@ fn cmd_read(world: &mut World, player: &Player, name: &str) -> StatusResult {
     }
 }
 
+/// Smell, listen to, or touch an entity.
+fn cmd_sense(world: &World, id: ID, prose_type: ProseType) -> StatusResult {
+    visual::sense(world, id, prose_type);
+    Ok(Normal)
+}
+
+/// Smell, listen to, or touch a visible thing.
+fn cmd_sense_thing(
+    world: &World,
+    player: &Player,
+    name: &str,
+    prose_type: ProseType,
+) -> StatusResult {
+    if let Some(thing) = find_noun(world, phys::visible(world, player.id), name) {
+        cmd_sense(world, thing, prose_type)
+    } else {
+        Err("You don't see any such thing.".into())
+    }
+}
+
+/// Can the thing be opened and closed?  Containers can, as can anything that's closed.
+fn can_open(world: &World, thing: ID) -> bool {
+    world.has_inventory(thing) || world.has_flag(thing, Closed)
+}
+
+/// Open a visible container.
+fn cmd_open(world: &mut World, player: &Player, name: &str) -> StatusResult {
+    let thing = find_noun(world, phys::visible(world, player.id), name)
+        .ok_or_else(|| "You don't see any such thing.".to_string())?;
+
+    if thing == player.id || !can_open(world, thing) {
+        Err("You can't open that.".into())
+    } else if !world.has_flag(thing, Closed) {
+        Err("It's already open.".into())
+    } else if world.has_flag(thing, Locked) {
+        Err("It's locked.".into())
+    } else {
+        world.unset_flag(thing, Closed);
+        visual::act(&format!("You open the {}.", world.things[&thing].noun));
+        Ok(Normal)
+    }
+}
+
+/// Close a visible container.
+fn cmd_close(world: &mut World, player: &Player, name: &str) -> StatusResult {
+    let thing = find_noun(world, phys::visible(world, player.id), name)
+        .ok_or_else(|| "You don't see any such thing.".to_string())?;
+
+    if thing == player.id || !can_open(world, thing) {
+        Err("You can't close that.".into())
+    } else if world.has_flag(thing, Closed) {
+        Err("It's already closed.".into())
+    } else {
+        world.set_flag(thing, Closed);
+        visual::act(&format!("You close the {}.", world.things[&thing].noun));
+        Ok(Normal)
+    }
+}
+
 /// Gets a thing from the location's inventory.
 fn cmd_get(world: &mut World, player: &Player, noun: &str) -> StatusResult {
     // Does he already have it?
@@ -199,26 +558,61 @@ fn cmd_get(world: &mut World, player: &Player, noun: &str) -> StatusResult {
         return Err("You already have that.".into());
     }
 
-    if find_noun(world, phys::immovable(world, player.loc), noun).is_some() {
-        return Err("You can't take that!".into());
-    }
-
-    if let Some(thing) = find_noun(world, phys::gettable(world, player.id), noun) {
+    if let Some(thing) = find_noun(world, phys::contents(world, player.loc), noun) {
         // Get the thing.
         phys::get_thing(world, player.id, thing)?;
+
+        // A guard might have prevented it.
+        if phys::owns(world, player.id, thing) {
+            world.set_flag(thing, Handled);
+        }
         return Ok(Normal);
     }
 
     Err("You don't see any such thing.".into())
 }
 
+/// Takes a thing out of a visible container, e.g., "take coin from chest".
+fn cmd_take_from(
+    world: &mut World,
+    player: &Player,
+    name: &str,
+    container: &str,
+) -> StatusResult {
+    // FIRST, find the container.
+    let cid = find_noun(world, phys::visible(world, player.id), container)
+        .ok_or_else(|| "You don't see any such thing.".to_string())?;
+
+    if cid == player.id || !world.has_inventory(cid) {
+        return Err("There's nothing in that.".into());
+    }
+
+    if world.has_flag(cid, Closed) {
+        return Err(format!("The {} is closed.", world.things[&cid].noun));
+    }
+
+    // NEXT, find the thing within it.
+    if let Some(thing) = find_noun(world, phys::contents(world, cid), name) {
+        phys::take_from(world, player.id, thing, cid)?;
+
+        // A guard might have prevented it.
+        if phys::owns(world, player.id, thing) {
+            world.set_flag(thing, Handled);
+        }
+        Ok(Normal)
+    } else {
+        Err(format!("There's no {} in the {}.", name, world.things[&cid].noun))
+    }
+}
+
 /// Drops a thing you're carrying
 fn cmd_drop(world: &mut World, player: &Player, noun: &str) -> StatusResult {
     if let Some(thing) = find_noun(world, phys::droppable(world, player.id), noun) {
         // Drop the thing
-        phys::put_in(world, thing, player.loc);
-        visual::act("Dropped.");
+        phys::drop_thing(world, player.id, thing)?;
         Ok(Normal)
+    } else if is_bound(world, player, noun) {
+        Err("You can't seem to let go of it.".into())
     } else if find_noun(world, phys::scenery(world, player.id), noun).is_some() {
         Err("You can't drop that!".into())
     } else if find_noun(world, phys::visible(world, player.id), noun).is_some() {
@@ -228,6 +622,112 @@ fn cmd_drop(world: &mut World, player: &Player, noun: &str) -> StatusResult {
     }
 }
 
+/// Uses one visible thing on another, e.g., a key on a door.
+fn cmd_use_on(world: &mut World, player: &Player, tool: &str, target: &str) -> StatusResult {
+    let visible = phys::visible(world, player.id);
+
+    match (find_noun(world, visible.clone(), tool), find_noun(world, visible, target)) {
+        (Some(tool), Some(target)) => {
+            phys::use_on(world, player.id, tool, target)?;
+            Ok(Normal)
+        }
+        _ => Err("You don't see any such thing.".into()),
+    }
+}
+
+/// Pushes, pulls, etc., a visible thing.
+fn cmd_manipulate(world: &mut World, player: &Player, name: &str, how: Manipulation) -> StatusResult {
+    if let Some(thing) = find_noun(world, phys::visible(world, player.id), name) {
+        phys::manipulate(world, player.id, thing, how)?;
+        Ok(Normal)
+    } else {
+        Err("You don't see any such thing.".into())
+    }
+}
+
+/// Is the named thing one the player is carrying but can't let go of?
+fn is_bound(world: &World, player: &Player, noun: &str) -> bool {
+    find_noun(world, phys::contents(world, player.id), noun)
+        .is_some_and(|thing| world.has_flag(thing, Bound))
+}
+
+/// Gives a thing you're carrying to someone (or something) that can hold it.
+fn cmd_give(world: &mut World, player: &Player, noun: &str, recipient: &str) -> StatusResult {
+    let thing = match find_noun(world, phys::droppable(world, player.id), noun) {
+        Some(thing) => thing,
+        None if is_bound(world, player, noun) => {
+            return Err("You can't seem to let go of it.".into());
+        }
+        None => return Err("You aren't carrying that.".into()),
+    };
+
+    match find_noun(world, phys::visible(world, player.id), recipient) {
+        Some(rid) if rid != player.id && world.has_inventory(rid) => {
+            phys::give_thing(world, player.id, thing, rid)?;
+            Ok(Normal)
+        }
+        Some(_) => Err("You can't give anything to that.".into()),
+        None => Err("You don't see any such thing.".into()),
+    }
+}
+
+/// Display the player's score.
+fn cmd_score(world: &World) -> StatusResult {
+    visual::prose(&format!("Your score is {}, in {} turns.", world.score, world.clock))
+        .when(!world.notify, "Score notifications are off.")
+        .para();
+    Ok(Normal)
+}
+
+/// Turn score notifications on or off.
+fn cmd_notify(world: &mut World, flag: bool) -> StatusResult {
+    world.notify = flag;
+    if flag {
+        visual::act("Score notifications are on.");
+    } else {
+        visual::act("Score notifications are off.");
+    }
+    Ok(Normal)
+}
+
+/// List the currently defined aliases.
+fn cmd_list_aliases(world: &World) -> StatusResult {
+    if world.aliases.is_empty() {
+        visual::info("No aliases are defined.");
+    } else {
+        let mut aliases: Vec<_> = world.aliases.iter().collect();
+        aliases.sort();
+
+        for (name, expansion) in aliases {
+            visual::info(&format!("{} = {}", name, expansion));
+        }
+    }
+
+    Ok(Normal)
+}
+
+/// Define an alias for a command.  Aliases can't shadow real verbs.
+fn cmd_alias(world: &mut World, name: &str, expansion: &[&str]) -> StatusResult {
+    if world.verbs.contains(name) || Dir::from_str(name).is_some() {
+        return Err(format!("You can't redefine \"{}\".", name));
+    }
+
+    let expansion = expansion.join(" ");
+    visual::act(&format!("Alias defined: {} = {}", name, expansion));
+    world.aliases.insert(name.into(), expansion);
+    Ok(Normal)
+}
+
+/// Remove an alias.
+fn cmd_unalias(world: &mut World, name: &str) -> StatusResult {
+    if world.aliases.remove(name).is_some() {
+        visual::act(&format!("Alias removed: {}", name));
+        Ok(Normal)
+    } else {
+        Err(format!("There's no alias called \"{}\".", name))
+    }
+}
+
 /// Undo the last command the game
 fn cmd_undo(game: &mut Game) -> StatusResult {
     if game.has_undo() {
@@ -247,7 +747,7 @@ fn cmd_restart() -> StatusResult {
 /// Quit the game.
 fn cmd_quit() -> StatusResult {
     visual::act("Bye, then.");
-    ::std::process::exit(0);
+    Ok(Quit)
 }
 
 //------------------------------------------------------------------------------
@@ -256,26 +756,115 @@ fn cmd_quit() -> StatusResult {
 /// Handle debugging commands.
 fn handle_debug_command(game: &mut Game, player: &Player, cmd: &Command) -> StatusResult {
     let words: Vec<&str> = cmd.words.iter().map(|s| s.as_ref()).collect();
+
+    // FIRST, handle the commands that need the game as a whole.
+    match words.as_slice() {
+        ["checkpoint", "save", name] => return cmd_debug_checkpoint_save(game, name),
+        ["checkpoint", "load", name] => return cmd_debug_checkpoint_load(game, name),
+        #[cfg(debug_assertions)]
+        ["diff"] => return cmd_debug_diff(game),
+        ["debug", "on"] => return cmd_debug_mode(game, true),
+        ["debug", "off"] => return cmd_debug_mode(game, false),
+        _ => (),
+    }
+
+    // NEXT, handle the commands that work on the world.
     let world = &mut game.world;
 
     match words.as_slice() {
         ["list"] => cmd_debug_list(world),
+        ["rooms"] => cmd_debug_rooms(world),
+        ["verbs"] => cmd_debug_verbs(world),
+        ["stats"] => cmd_debug_stats(world),
         ["dump", id_arg] => cmd_debug_dump(world, id_arg),
+        ["rules", id_arg] => cmd_debug_rules(world, id_arg),
         ["look", id_arg] => cmd_debug_look(world, id_arg),
         ["examine", id_arg] => cmd_debug_examine(world, id_arg),
         ["go", id_arg] => cmd_debug_go(world, player, id_arg),
+        ["go!", id_arg] => cmd_debug_go_with_rules(world, player, id_arg),
+        ["seed"] => cmd_debug_seed(world),
+        ["seed", seed_arg] => cmd_debug_reseed(world, seed_arg),
+        ["set", id_arg, flag_arg] => cmd_debug_set(world, id_arg, flag_arg),
+        ["unset", id_arg, flag_arg] => cmd_debug_unset(world, id_arg, flag_arg),
 
         // Error
         _ => Err("I don't understand.".into()),
     }
 }
 
+/// Turn diagnostics for failed commands on or off.
+fn cmd_debug_mode(game: &mut Game, flag: bool) -> StatusResult {
+    game.debug = flag;
+    visual::info(if flag { "Debug mode on." } else { "Debug mode off." });
+    Ok(Normal)
+}
+
+/// List the changes made by the previous turn, i.e., since the undo info was saved.
+/// Available only in debug builds.
+#[cfg(debug_assertions)]
+fn cmd_debug_diff(game: &Game) -> StatusResult {
+    let before = game.undo_info.as_ref().ok_or_else(|| "No previous turn.".to_string())?;
+    let changes = before.diff(&game.world);
+
+    if changes.is_empty() {
+        visual::info("No changes.");
+    } else {
+        for change in changes {
+            visual::info(&change);
+        }
+    }
+
+    Ok(Normal)
+}
+
+/// Save the current world as a named checkpoint.
+fn cmd_debug_checkpoint_save(game: &mut Game, name: &str) -> StatusResult {
+    game.save_checkpoint(name);
+    visual::info(&format!("Saved checkpoint: {}", name));
+    Ok(Normal)
+}
+
+/// Restore the world from a named checkpoint.
+fn cmd_debug_checkpoint_load(game: &mut Game, name: &str) -> StatusResult {
+    if game.load_checkpoint(name) {
+        visual::info(&format!("Loaded checkpoint: {}", name));
+        Ok(Normal)
+    } else {
+        Err(format!("No such checkpoint: {}", name))
+    }
+}
+
+/// List the rules that refer to an entity.
+fn cmd_debug_rules(world: &World, id_arg: &str) -> StatusResult {
+    let id = parse_id(world, id_arg)?;
+    debug::rules_for(world, id);
+    Ok(Normal)
+}
+
 /// List all of the available entities.
 fn cmd_debug_list(world: &World) -> StatusResult {
     debug::list_world(world);
     Ok(Normal)
 }
 
+/// List all of the rooms, so that the user knows where he can go.
+fn cmd_debug_rooms(world: &World) -> StatusResult {
+    debug::list_rooms(world);
+    Ok(Normal)
+}
+
+/// List all of the available verbs and their synonyms.
+fn cmd_debug_verbs(world: &World) -> StatusResult {
+    debug::list_verbs(world);
+    Ok(Normal)
+}
+
+/// Print counts of the world's entities, rules, etc.
+fn cmd_debug_stats(world: &World) -> StatusResult {
+    debug::world_stats(world);
+    Ok(Normal)
+}
+
 /// Dump information about the given entity, provided the ID string is valid.
 fn cmd_debug_dump(world: &World, id_arg: &str) -> StatusResult {
     let id = parse_id(world, id_arg)?;
@@ -305,7 +894,7 @@ fn cmd_debug_examine(world: &World, id_arg: &str) -> StatusResult {
     }
 }
 
-/// Take the player to the room.
+/// Take the player to the room.  No guards are checked and no rules are fired.
 fn cmd_debug_go(world: &mut World, player: &Player, id_arg: &str) -> StatusResult {
     let loc = parse_id(world, id_arg)?;
     if world.is_room(loc) {
@@ -317,6 +906,81 @@ fn cmd_debug_go(world: &mut World, player: &Player, id_arg: &str) -> StatusResul
     }
 }
 
+/// Take the player to the room, and fire its EnterRoom rules.  Guards are
+/// not checked.
+fn cmd_debug_go_with_rules(world: &mut World, player: &Player, id_arg: &str) -> StatusResult {
+    cmd_debug_go(world, player, id_arg)?;
+    let loc = phys::loc(world, player.id);
+    rule::fire_event(world, &Event::EnterRoom(player.id, loc));
+    Ok(Normal)
+}
+
+/// Show the random number generator's seed.
+fn cmd_debug_seed(world: &World) -> StatusResult {
+    visual::info(&format!("Seed: {}", world.rng.seed()));
+    Ok(Normal)
+}
+
+/// Reseed the random number generator.
+fn cmd_debug_reseed(world: &mut World, seed_arg: &str) -> StatusResult {
+    let seed = seed_arg
+        .parse::<u64>()
+        .map_err(|_| format!("Not a seed: {}", seed_arg))?;
+    world.rng.reseed(seed);
+    visual::info(&format!("Seed: {}", seed));
+    Ok(Normal)
+}
+
+/// Set a flag on the entity.
+fn cmd_debug_set(world: &mut World, id_arg: &str, flag_arg: &str) -> StatusResult {
+    let id = parse_id(world, id_arg)?;
+    let flag = parse_flag(flag_arg);
+
+    if !world.has_flags(id) {
+        return Err(format!("Entity {} has no flags.", id));
+    }
+
+    world.set_flag(id, flag);
+    debug::dump_flags(world, id);
+    Ok(Normal)
+}
+
+/// Clear a flag from the entity.
+fn cmd_debug_unset(world: &mut World, id_arg: &str, flag_arg: &str) -> StatusResult {
+    let id = parse_id(world, id_arg)?;
+    let flag = parse_flag(flag_arg);
+
+    if !world.has_flags(id) {
+        return Err(format!("Entity {} has no flags.", id));
+    }
+
+    world.unset_flag(id, flag);
+    debug::dump_flags(world, id);
+    Ok(Normal)
+}
+
+/// Parse a token as a flag.  The engine's simple flags are matched by name, ignoring
+/// case; any other token is taken to be the name of a User flag.
+fn parse_flag(token: &str) -> Flag {
+    match token.to_lowercase().as_str() {
+        "fireonce" => FireOnce,
+        "fired" => Fired,
+        "dead" => Dead,
+        "immovable" => Immovable,
+        "scenery" => Scenery,
+        "bound" => Bound,
+        "handled" => Handled,
+        "closed" => Closed,
+        "locked" => Locked,
+        "dark" => Dark,
+        "lightsource" => LightSource,
+        "npc" => Npc,
+        // User flags require a &'static str; debugging commands are rare enough that
+        // leaking the name is harmless.
+        _ => User(Box::leak(token.to_string().into_boxed_str())),
+    }
+}
+
 /// Parse a token as an entity tag or ID, return an ID on success and
 /// an error result on failure.
 fn parse_id(world: &World, token: &str) -> Result<ID, String> {
@@ -345,9 +1009,14 @@ fn parse_id(world: &World, token: &str) -> Result<ID, String> {
 
 /// Finds a noun in the list of things.
 fn find_noun(world: &World, ids: BTreeSet<ID>, noun: &str) -> Option<ID> {
+    // The player can refer to himself in several ways.
+    if matches!(noun, "me" | "myself" | "yourself") && ids.contains(&world.pid) {
+        return Some(world.pid);
+    }
+
     for id in ids {
         let thingc = &world.things[&id];
-        if thingc.noun == noun {
+        if thingc.noun == noun || thingc.phrases.iter().any(|p| p == noun) {
             return Some(id);
         }
     }
@@ -429,3 +1098,375 @@ impl CommandHandler {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual::OutputEvent;
+    use crate::world_builder::WBEvent;
+    use crate::world_builder::WorldBuilder;
+
+    #[test]
+    fn enter_thing() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.room("there", "There").prose("You made it.");
+        wb.feature("portal", "portal", "portal").location("here").leads_to("there");
+        wb.feature("rock", "rock", "rock").location("here");
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("here") };
+
+        assert!(cmd_enter(&mut world, &player, "rock").is_err());
+        assert!(cmd_enter(&mut world, &player, "portal").is_ok());
+        assert_eq!(phys::loc(&world, player.id), world.lookup("there"));
+    }
+
+    #[test]
+    fn use_on() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("key", "key", "key").location(crate::world_builder::PLAYER);
+        wb.thing("door", "door", "door").location("here");
+        wb.thing("rock", "rock", "rock").location("here");
+        wb.on(&WBEvent::UseOn("key", "door")).print("Click.");
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("here") };
+
+        let events = visual::capture(|| {
+            cmd_use_on(&mut world, &player, "key", "door").unwrap();
+            cmd_use_on(&mut world, &player, "key", "rock").unwrap();
+        });
+        assert_eq!(
+            events,
+            vec![
+                OutputEvent::Info("Click.".into()),
+                OutputEvent::Action("Nothing happens.".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_events() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("altar", "altar", "altar").location("here");
+        wb.verb("chant", &|_, _, script| {
+            script.fire_custom("ritual_complete", "altar");
+            Ok(())
+        });
+        wb.on(&WBEvent::Custom("ritual_complete", "altar")).print("The altar glows.");
+        let mut game = Game { world: wb.world(), ..Game::default() };
+
+        let events = visual::capture(|| system(&mut game, "chant"));
+        assert_eq!(events, vec![OutputEvent::Info("The altar glows.".into())]);
+    }
+
+    #[test]
+    fn debug_mode_diagnostics() {
+        let mut game = Game::default();
+        system(&mut game, "get note");
+        system(&mut game, "!debug on");
+
+        let events = visual::capture(|| system(&mut game, "read note"));
+        let guard = game.world.lookup("allow-read-note");
+        let expected = OutputEvent::Info(format!("[debug] Denied by: [{}] allow-read-note", guard));
+        assert!(events.contains(&expected), "{:?}", events);
+
+        system(&mut game, "!debug off");
+        let events = visual::capture(|| system(&mut game, "get frobozz"));
+        assert_eq!(events, vec![OutputEvent::Error("You don't see any such thing.".into())]);
+    }
+
+    #[test]
+    fn quit_ends_game() {
+        let mut game = Game::default();
+        assert!(!game.is_over());
+
+        system(&mut game, "quit");
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn easter_egg() {
+        let mut game = Game::default();
+        let events = visual::capture(|| system(&mut game, "xyzzy"));
+        assert_eq!(events, vec![OutputEvent::Info("A hollow voice says 'Fool.'".into())]);
+    }
+
+    #[test]
+    fn read_and_examine_in_the_dark() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("cave");
+        wb.room("cave", "Cave").flag(Dark);
+        wb.thing("note", "note", "note")
+            .location(crate::world_builder::PLAYER)
+            .on_read("Hello.");
+        wb.thing("bag", "bag", "bag").location(crate::world_builder::PLAYER).inventory();
+        wb.thing("coin", "coin", "coin").inside("bag");
+        wb.thing("lamp", "lamp", "lamp").flag(LightSource);
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("cave") };
+
+        assert_eq!(cmd_read(&mut world, &player, "note").unwrap_err(), "It's too dark to read.");
+        assert_eq!(
+            cmd_examine(&mut world, &player, "note").unwrap_err(),
+            "It's too dark to see that."
+        );
+        assert_eq!(
+            cmd_examine_in(&mut world, &player, "coin", "bag").unwrap_err(),
+            "It's too dark to see that."
+        );
+
+        let lamp = world.lookup("lamp");
+        phys::put_in(&mut world, lamp, player.id);
+        assert!(cmd_read(&mut world, &player, "note").is_ok());
+        visual::capture(|| assert!(cmd_examine_in(&mut world, &player, "coin", "bag").is_ok()));
+    }
+
+    #[test]
+    fn readable_container() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("journal", "journal", "journal")
+            .location(crate::world_builder::PLAYER)
+            .inventory()
+            .flag(Closed)
+            .on_read("Dear diary...");
+        wb.thing("flower", "pressed flower", "flower").inside("journal");
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("here") };
+        let journal = world.lookup("journal");
+
+        // A closed book can be read.
+        assert!(cmd_read(&mut world, &player, "journal").is_ok());
+        assert!(cmd_examine_in(&mut world, &player, "flower", "journal").is_err());
+
+        assert!(cmd_open(&mut world, &player, "journal").is_ok());
+        assert!(cmd_read(&mut world, &player, "journal").is_ok());
+        assert!(cmd_examine_in(&mut world, &player, "flower", "journal").is_ok());
+        assert_eq!(
+            cmd_examine_in(&mut world, &player, "flower", "me").unwrap_err(),
+            "There's nothing in that."
+        );
+        assert!(cmd_close(&mut world, &player, "journal").is_ok());
+        assert!(world.has_flag(journal, Closed));
+
+        // A locked one can't be read or opened.
+        world.set_flag(journal, Locked);
+        assert_eq!(cmd_open(&mut world, &player, "journal").unwrap_err(), "It's locked.");
+        assert_eq!(cmd_read(&mut world, &player, "journal").unwrap_err(), "It's locked.");
+    }
+
+    #[test]
+    fn take_from_container() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("chest", "chest", "chest").location("here").inventory().flag(Closed);
+        wb.thing("coin", "coin", "coin").inside("chest");
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("here") };
+        let coin = world.lookup("coin");
+
+        assert_eq!(
+            cmd_take_from(&mut world, &player, "coin", "chest").unwrap_err(),
+            "The chest is closed."
+        );
+
+        assert!(cmd_open(&mut world, &player, "chest").is_ok());
+        assert_eq!(
+            cmd_take_from(&mut world, &player, "ruby", "chest").unwrap_err(),
+            "There's no ruby in the chest."
+        );
+        assert!(cmd_take_from(&mut world, &player, "coin", "chest").is_ok());
+        assert!(phys::owns(&world, player.id, coin));
+        assert!(world.has_flag(coin, Handled));
+    }
+
+    #[test]
+    fn denied_get_is_not_handled() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("chest", "chest", "chest").location("here").inventory();
+        wb.thing("coin", "coin", "coin").location("here");
+        wb.thing("ruby", "ruby", "ruby").inside("chest");
+        wb.allow(&WBEvent::GetThing("coin")).print("It's glued down.");
+        wb.allow(&WBEvent::TakeFrom("ruby", "chest")).print("It's cursed.");
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("here") };
+        let coin = world.lookup("coin");
+        let ruby = world.lookup("ruby");
+
+        visual::capture(|| {
+            assert!(cmd_get(&mut world, &player, "coin").is_ok());
+            assert!(cmd_take_from(&mut world, &player, "ruby", "chest").is_ok());
+        });
+        assert!(!phys::owns(&world, player.id, coin));
+        assert!(!world.has_flag(coin, Handled));
+        assert!(!phys::owns(&world, player.id, ruby));
+        assert!(!world.has_flag(ruby, Handled));
+    }
+
+    #[test]
+    fn go_no_exit() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here").no_exit(Dir::Up, "The cliff is too steep.");
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("here") };
+
+        assert_eq!(cmd_go(&mut world, &player, Dir::Up).unwrap_err(), "The cliff is too steep.");
+        assert_eq!(cmd_go(&mut world, &player, Dir::Down).unwrap_err(), "You can't go that way.");
+    }
+
+    #[test]
+    fn go_dead_end_vs_guard() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here")
+            .dead_end(Dir::North, "The trees are too thick.")
+            .link(Dir::East, "there");
+        wb.room("there", "There").prose("Over there.");
+        wb.allow(&WBEvent::EnterRoom("there")).unless(&|_| true).print("A troll blocks the way.");
+        let mut world = wb.world();
+        let here = world.lookup("here");
+        let player = Player { id: world.pid, loc: here };
+
+        // A dead end shows its prose; a guard shows its own.  Either way, the
+        // player stays put.
+        let events = visual::capture(|| {
+            cmd_go(&mut world, &player, Dir::North).unwrap();
+            cmd_go(&mut world, &player, Dir::East).unwrap();
+        });
+        assert_eq!(
+            events,
+            vec![
+                OutputEvent::Info("The trees are too thick.".into()),
+                OutputEvent::Info("A troll blocks the way.".into())
+            ]
+        );
+        assert_eq!(phys::loc(&world, player.id), here);
+    }
+
+    #[test]
+    fn alias_expands_first_word() {
+        let mut game = Game::default();
+        system(&mut game, "alias peek examine note");
+        assert!(game.world.aliases.contains_key("peek"));
+
+        let events = visual::capture(|| system(&mut game, "peek"));
+        assert!(matches!(&events[..], [OutputEvent::Body(_)]), "{:?}", events);
+
+        let events = visual::capture(|| system(&mut game, "alias look examine note"));
+        assert!(matches!(&events[..], [OutputEvent::Error(_)]), "{:?}", events);
+
+        system(&mut game, "unalias peek");
+        assert!(game.world.aliases.is_empty());
+    }
+
+    #[test]
+    fn multi_word_nouns() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("room");
+        wb.room("room", "Room").prose("A room.");
+        wb.thing("lantern", "brass lantern", "lantern")
+            .phrase("brass lantern")
+            .location("room")
+            .on_examine("A brass lantern.");
+        let mut game = Game { world: wb.world(), ..Game::default() };
+
+        let events = visual::capture(|| system(&mut game, "examine \"brass lantern\""));
+        assert!(matches!(&events[..], [OutputEvent::Body(_)]), "{:?}", events);
+
+        system(&mut game, "get brass lantern");
+        let lantern = game.world.lookup("lantern");
+        assert_eq!(phys::loc(&game.world, lantern), game.world.pid);
+    }
+
+    #[test]
+    fn sensory_verbs() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("room");
+        wb.room("room", "Room").prose("A room.").on_sound("Birds chirp.");
+        wb.thing("rose", "rose", "rose").location("room").on_smell("It smells sweet.");
+        let mut game = Game { world: wb.world(), ..Game::default() };
+
+        let events = visual::capture(|| system(&mut game, "smell rose"));
+        assert_eq!(events, vec![OutputEvent::Body("It smells sweet.".into())]);
+
+        let events = visual::capture(|| system(&mut game, "listen"));
+        assert_eq!(events, vec![OutputEvent::Body("Birds chirp.".into())]);
+
+        let events = visual::capture(|| system(&mut game, "feel rose"));
+        assert_eq!(events, vec![OutputEvent::Info("You feel nothing unusual.".into())]);
+    }
+
+    #[test]
+    fn examine_and_get_set_handled() {
+        let mut game = Game::default();
+        assert!(!game.world.handled("note"));
+
+        system(&mut game, "examine note");
+        assert!(game.world.handled("note"));
+
+        system(&mut game, "undo");
+        assert!(!game.world.handled("note"));
+
+        system(&mut game, "get note");
+        assert!(game.world.handled("note"));
+    }
+
+    #[test]
+    fn examine_in_container() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("chest", "chest", "chest").location("here").inventory();
+        wb.thing("coin", "gold coin", "coin").location("chest").on_examine("Gold!");
+        wb.thing("copper", "copper coin", "coin").location("here").on_examine("Copper.");
+        let mut world = wb.world();
+        let player = Player { id: world.pid, loc: world.lookup("here") };
+
+        let events = visual::capture(|| {
+            cmd_examine_in(&mut world, &player, "coin", "chest").unwrap();
+        });
+        assert_eq!(events, vec![OutputEvent::Body("Gold!".into())]);
+
+        world.set_flag(world.lookup("chest"), Closed);
+        assert_eq!(
+            cmd_examine_in(&mut world, &player, "coin", "chest").unwrap_err(),
+            "The chest is closed."
+        );
+    }
+
+    #[test]
+    fn go_north_from_clearing() {
+        let mut game = Game::default();
+        let events = visual::capture(|| system(&mut game, "north"));
+
+        assert!(
+            matches!(&events[..], [OutputEvent::Info(text)] if text.contains("deja vu")),
+            "{:?}",
+            events
+        );
+    }
+
+    #[test]
+    fn examine_self() {
+        for cmd in &["examine self", "examine me", "x myself", "x yourself"] {
+            let mut game = Game::default();
+            let events = visual::capture(|| system(&mut game, cmd));
+            assert!(
+                matches!(&events[0], OutputEvent::Body(text) if text.starts_with("You've got all the usual bits.")),
+                "{}: {:?}",
+                cmd,
+                events
+            );
+        }
+    }
+}
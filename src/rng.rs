@@ -0,0 +1,105 @@
+//! A small, deterministic random number generator.
+//!
+//! All randomness in the engine should be drawn from the world's `Rng`, so that
+//! undo, checkpoints, and replays reproduce the same outcomes.  The generator is
+//! xorshift64*, which is plenty for a text adventure.
+
+/// The seed used when the scenario doesn't provide one.
+pub const DEFAULT_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// A seeded pseudo-random number generator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    /// The seed the generator was last seeded with
+    seed: u64,
+
+    /// The generator's current state; never zero.
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+impl Rng {
+    /// Creates a new generator with the given seed.
+    pub fn new(seed: u64) -> Rng {
+        let mut rng = Rng { seed: 0, state: 0 };
+        rng.reseed(seed);
+        rng
+    }
+
+    /// Returns the seed the generator was last seeded with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Reseeds the generator.  A zero seed is replaced with a fixed nonzero
+    /// state, as xorshift can't escape zero.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.state = if seed == 0 { DEFAULT_SEED } else { seed };
+    }
+
+    /// Returns the next pseudo-random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in the range 0..n.
+    ///
+    /// * Panics if n is zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "Rng::below called with zero");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns true with the given percent chance.
+    pub fn chance(&mut self, percent: u32) -> bool {
+        (self.below(100) as u32) < percent
+    }
+
+    /// Picks an item from the slice, or None if it's empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let xs: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..10).map(|_| b.next_u64()).collect();
+        assert_eq!(xs, ys);
+
+        b.reseed(43);
+        assert_ne!(a.next_u64(), b.next_u64());
+        assert_eq!(b.seed(), 43);
+    }
+
+    #[test]
+    fn below_and_pick() {
+        let mut rng = Rng::new(0);
+        for _ in 0..100 {
+            assert!(rng.below(6) < 6);
+        }
+
+        let empty: [u32; 0] = [];
+        assert_eq!(rng.pick(&empty), None);
+        assert_eq!(rng.pick(&[7]), Some(&7));
+    }
+}
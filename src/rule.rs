@@ -3,19 +3,27 @@
 use crate::entity::ID;
 use crate::types::Event;
 use crate::types::Flag::*;
+use crate::visual;
 use crate::world::World;
+use crate::world::LIMBO;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::thread::LocalKey;
 
 /// Executes the guard that applies to the given event (if any), and returns
 /// whether or not the event is allowed.  If the event is denied, the guard's
 /// script is executed.
 pub fn allows(world: &mut World, event: &Event) -> bool {
-    for id in world.rules.keys().cloned() {
+    for id in ordered_rules(world) {
         let rulec = &world.rules[&id];
         if rulec.is_guard && event == &rulec.event {
-            if (rulec.predicate)(world) {
+            let owner = rulec.owner;
+            let predicate = rulec.predicate;
+            if as_owner(owner, || predicate(world)) {
                 // The action is not allowed; execute the script.
-                let script = rulec.script.clone();
-                script.execute(world);
+                DENIED_BY.with(|d| d.set(Some(id)));
+                let script = world.rules[&id].script.clone();
+                as_owner(owner, || script.execute(world));
                 return false;
             } else {
                 // The action is allowed.
@@ -29,35 +37,306 @@ pub fn allows(world: &mut World, event: &Event) -> bool {
 }
 
 /// Fire all rules for the given event, and execute those whose predicates are met.
-pub fn fire_event(world: &mut World, event: &Event) {
-    fire_events(world, &[event]);
+/// Returns true if any rule fired.
+pub fn fire_event(world: &mut World, event: &Event) -> bool {
+    fire_events(world, &[event])
+}
+
+/// The maximum depth of nested event firings, e.g., a rule that fires an event
+/// that triggers a rule that fires an event.
+const MAX_EVENT_DEPTH: usize = 32;
+
+thread_local! {
+    /// The current depth of nested event firings.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+
+    /// The guard that most recently denied an event, for debugging.
+    static DENIED_BY: Cell<Option<ID>> = const { Cell::new(None) };
+
+    /// The owner of the rule currently being evaluated or executed, if any.
+    static OWNER: Cell<Option<ID>> = const { Cell::new(None) };
+}
+
+/// Returns the owner of the rule currently being evaluated or executed, if it
+/// has one.
+pub fn owner() -> Option<ID> {
+    OWNER.with(|o| o.get())
+}
+
+/// Calls the function with the given rule owner as the current owner, restoring the
+/// previous owner afterwards, since rules can fire events that trigger other rules.
+fn as_owner<T>(owner: Option<ID>, f: impl FnOnce() -> T) -> T {
+    let prev = OWNER.with(|o| o.replace(owner));
+    let _restore = Restore(&OWNER, prev);
+    f()
+}
+
+/// Restores a thread-local rule state variable to its saved value when dropped,
+/// so that the state is correct even if a rule panics and the game recovers.
+struct Restore<T: Copy + 'static>(&'static LocalKey<Cell<T>>, T);
+
+impl<T: Copy + 'static> Drop for Restore<T> {
+    fn drop(&mut self) {
+        let value = self.1;
+        self.0.with(|cell| cell.set(value));
+    }
+}
+
+/// Returns the ID of the guard that most recently denied an event, if any, and
+/// forgets it.
+pub fn take_denial() -> Option<ID> {
+    DENIED_BY.with(|d| d.take())
 }
 
 /// Fire all rules whose events are in the events set, and execute those whose
-/// predicates are met.
-pub fn fire_events(world: &mut World, events: &[&Event]) {
-    let rules: Vec<ID> = world
-        .rules
-        .keys()
-        .cloned()
+/// predicates are met.  Returns true if any rule fired.  Events fired by rules
+/// nest; once the nesting reaches MAX_EVENT_DEPTH, no further rules fire.
+pub fn fire_events(world: &mut World, events: &[&Event]) -> bool {
+    let depth = DEPTH.with(|d| d.get());
+    if depth >= MAX_EVENT_DEPTH {
+        visual::error(&format!(
+            "Warning: events nested more than {} deep; ignoring {:?}",
+            MAX_EVENT_DEPTH, events
+        ));
+        return false;
+    }
+
+    DEPTH.with(|d| d.set(depth + 1));
+    let _restore = Restore(&DEPTH, depth);
+    fire_matching_rules(world, events)
+}
+
+/// Fires the rules whose events are in the events set and whose predicates are met.
+fn fire_matching_rules(world: &mut World, events: &[&Event]) -> bool {
+    let rules: Vec<ID> = ordered_rules(world)
+        .into_iter()
         .filter(|id| !world.has_flag(*id, FireOnce) || !world.has_flag(*id, Fired))
+        .filter(|id| world.rules[id].limit.is_none_or(|n| world.rules[id].fire_count < n))
+        .filter(|id| !is_cooling_down(world, *id))
         .collect();
 
+    let mut fired = false;
+
     for id in rules {
         let rulec = &world.rules[&id];
+        let predicate = rulec.predicate;
         if !rulec.is_guard
             && events.contains(&&rulec.event)
-            && (rulec.predicate)(world)
+            && as_owner(rulec.owner, || predicate(world))
         {
             fire_rule(world, id);
+            fired = true;
         }
     }
+
+    fired
+}
+
+/// Returns the IDs of all active rules in firing order: by priority, highest first,
+/// and then in order of creation.  Rules whose owners have been removed from the
+/// world (i.e., put in LIMBO) are inactive.
+fn ordered_rules(world: &World) -> Vec<ID> {
+    let mut rules: Vec<ID> = world
+        .rules
+        .keys()
+        .cloned()
+        .filter(|id| is_active(world, *id))
+        .collect();
+    rules.sort_by_key(|id| (Reverse(world.rules[id].priority), *id));
+    rules
+}
+
+/// Is the rule active?  I.e., does it have no owner, or an owner that is still in the
+/// world?  An owner with no location isn't in the world.
+fn is_active(world: &World, id: ID) -> bool {
+    match world.rules[&id].owner {
+        Some(owner) => world.locations.get(&owner).is_some_and(|locc| locc.id != LIMBO),
+        None => true,
+    }
 }
 
+/// Is the rule cooling down?  I.e., has it fired too recently to fire again?
+fn is_cooling_down(world: &World, id: ID) -> bool {
+    let rulec = &world.rules[&id];
+    rulec.last_fired.is_some_and(|t| world.clock - t < rulec.cooldown)
+}
 
 /// Execute the given rule
 fn fire_rule(world: &mut World, id: ID) {
     let script = world.rules[&id].script.clone();
-    script.execute(world);
+    let rulec = world.rules.get_mut(&id).unwrap();
+    rulec.fire_count += 1;
+    rulec.last_fired = Some(world.clock);
+    let owner = rulec.owner;
+    as_owner(owner, || script.execute(world));
     world.set_flag(id, Fired);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phys;
+    use crate::types::Flag::User;
+    use crate::world::WorldQuery;
+    use crate::world_builder::WBEvent::*;
+    use crate::world_builder::*;
+
+    #[test]
+    fn guards_and_rules_fire() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("coin", "coin", "coin").location("here");
+        wb.allow(&GetThing("coin"))
+            .unless(&|w| w.has("coin", User("GLUED")))
+            .set_flag("coin", User("DENIED"));
+        wb.on(&GetThing("coin")).set_flag("coin", User("GOTTEN"));
+        let mut world = wb.world();
+        let event = Event::GetThing(world.pid, world.lookup("coin"));
+
+        // The guard allows the event, and the rule fires.
+        assert!(allows(&mut world, &event));
+        fire_event(&mut world, &event);
+        assert!(world.has("coin", User("GOTTEN")));
+
+        // The guard denies the event, and executes its script.
+        world.set_flag(world.lookup("coin"), User("GLUED"));
+        assert!(!allows(&mut world, &event));
+        assert!(world.has("coin", User("DENIED")));
+    }
+
+    #[test]
+    fn rules_fire_by_priority() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.rule("ambiance").set_flag(PLAYER, User("BORED"));
+        wb.rule("game-over")
+            .priority(10)
+            .when(&|w| !w.has(PLAYER, User("BORED")))
+            .set_flag(PLAYER, User("GAME_OVER"));
+        let mut world = wb.world();
+
+        // game-over fires first, even though it was created second.
+        fire_event(&mut world, &Event::Turn);
+        assert!(world.has(PLAYER, User("GAME_OVER")));
+    }
+
+    #[test]
+    fn rules_fire_limited_times() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.rule("fountain").times(3).print("A coin drops out.");
+        let mut world = wb.world();
+
+        let fired: Vec<bool> = (0..4).map(|_| fire_event(&mut world, &Event::Turn)).collect();
+        assert_eq!(fired, vec![true, true, true, false]);
+        assert_eq!(world.rules[&world.lookup("fountain")].fire_count, 3);
+    }
+
+    #[test]
+    fn rules_cool_down() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.rule("shout").cooldown(3).set_flag(PLAYER, User("DEAFENED"));
+        let mut world = wb.world();
+
+        let mut fired = Vec::new();
+        for _ in 0..7 {
+            fired.push(fire_event(&mut world, &Event::Turn));
+            world.clock += 1;
+        }
+        assert_eq!(fired, vec![true, false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn rules_can_fire_events() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("idol", "idol", "idol").location("here");
+        wb.thing("altar", "altar", "altar").location("here");
+        wb.on(&GetThing("idol")).fire_event(&UseOn("idol", "altar"));
+        wb.on(&UseOn("idol", "altar")).set_flag("altar", User("SPRUNG"));
+        let mut world = wb.world();
+        let pid = world.pid;
+        let idol = world.lookup("idol");
+
+        assert!(fire_event(&mut world, &Event::GetThing(pid, idol)));
+        assert!(world.has("altar", User("SPRUNG")));
+    }
+
+    #[test]
+    fn mutually_triggering_rules_terminate() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("ping", "ping", "ping").location("here");
+        wb.thing("pong", "pong", "pong").location("here");
+        wb.on(&UseOn("ping", "pong")).fire_event(&UseOn("pong", "ping"));
+        wb.on(&UseOn("pong", "ping")).fire_event(&UseOn("ping", "pong"));
+        let mut world = wb.world();
+        let pid = world.pid;
+        let ping = world.lookup("ping");
+        let pong = world.lookup("pong");
+
+        let events = visual::capture(|| {
+            assert!(fire_event(&mut world, &Event::UseOn(pid, ping, pong)));
+        });
+        assert_eq!(DEPTH.with(|d| d.get()), 0);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            visual::OutputEvent::Error(text) if text.starts_with("Warning: events nested more than 32 deep")
+        ));
+    }
+
+    #[test]
+    fn owned_rules_fire_while_owner_is_present() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("troll", "troll", "troll")
+            .location("here")
+            .rule(&Turn)
+            .when(&|w| w.loc("troll") == w.loc(PLAYER))
+            .set_flag(PLAYER, User("ATTACKED"));
+        let mut world = wb.world();
+        let troll = world.lookup("troll");
+        let here = world.lookup("here");
+
+        // The troll is gone, so its rule doesn't fire.
+        phys::take_out(&mut world, troll);
+        fire_event(&mut world, &Event::Turn);
+        assert!(!world.has(PLAYER, User("ATTACKED")));
+
+        // The troll is back.
+        phys::put_in(&mut world, troll, here);
+        fire_event(&mut world, &Event::Turn);
+        assert!(world.has(PLAYER, User("ATTACKED")));
+    }
+
+    #[test]
+    fn owned_rules_know_their_owner() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.room("there", "There");
+        for (troll, room) in &[("troll-1", "here"), ("troll-2", "there")] {
+            wb.thing(troll, "troll", "troll")
+                .location(room)
+                .rule(&Turn)
+                .when(&|w| w.here(&w.owner()))
+                .set_flag(THIS, User("SEES_PLAYER"));
+        }
+        let mut world = wb.world();
+
+        // Each troll's rule concerns itself, not the other troll.
+        fire_event(&mut world, &Event::Turn);
+        assert!(world.has("troll-1", User("SEES_PLAYER")));
+        assert!(!world.has("troll-2", User("SEES_PLAYER")));
+        assert_eq!(owner(), None);
+    }
+}
@@ -8,16 +8,47 @@ use crate::world::World;
 use crate::world_builder::*;
 use crate::world_builder::WBEvent::*;
 
+/// A function that builds the initial state of a scenario's game world.
+type ScenarioBuilder = fn() -> World;
+
+/// The registry of available scenarios, by name.
+const SCENARIOS: &[(&str, ScenarioBuilder)] = &[
+    ("woods", build),
+];
+
+/// The name of the scenario to play if none is specified.
+pub const DEFAULT: &str = "woods";
+
+/// Returns the names of the available scenarios.
+pub fn list() -> Vec<&'static str> {
+    SCENARIOS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Builds the initial state of the named scenario, or returns None if there
+/// is no such scenario.
+pub fn build_named(name: &str) -> Option<World> {
+    SCENARIOS
+        .iter()
+        .find(|(scenario, _)| *scenario == name)
+        .map(|(_, builder)| builder())
+}
+
+//-----------------------------------------------------------------------------------------------
+// The "woods" scenario
+
 // User-defined flags
 const DIRTY: Flag = User("DIRTY");
 const HAS_WATER: Flag = User("HAS_WATER");
 const TAKEN: Flag = User("TAKEN");
+const READ: Flag = User("READ");
 
-/// Build the initial state of the game world.
+/// Build the initial state of the "woods" scenario.
 pub fn build() -> World {
     // FIRST, create the world builder
     let mut wb = WorldBuilder::new();
 
+    wb.title("Bonaventure");
+
     // NEXT, configure the player
     wb.player()
         .location("clearing")
@@ -27,16 +58,14 @@ pub fn build() -> World {
         .location(PLAYER)
         .flag(DIRTY)
         .on_examine_hook(&|w,e,buff| {
-            if w.has(e, DIRTY) {
-                buff.puts("You don't remember what you were doing, but it must have been messy.");
-            } else {
-                buff.puts("Fresh and clean.");
-            }
+            buff.puts_or(
+                w.has(e, DIRTY),
+                "You don't remember what you were doing, but it must have been messy.",
+                "Fresh and clean.",
+            );
         })
         .on_scenery_hook(&|w,e,buff| {
-            if w.has(e, DIRTY) {
-                buff.puts("Your hands are kind of dirty, though.");
-            }
+            buff.puts_if(w.has(e, DIRTY), "Your hands are kind of dirty, though.");
         });
 
     // NEXT, create and configure the things in the world.
@@ -70,16 +99,29 @@ and that you don't want to go find it again.
         .location("clearing")
         .on_examine_hook(&|w,e,buff| {
             buff.puts("A note, on plain paper.");
-            if w.has(e, DIRTY) {
-                buff.puts("It looks pretty grubby; someone's been mishandling it.");
-            }
+            buff.puts_if(w.has(e, DIRTY), "It looks pretty grubby; someone's been mishandling it.");
         })
-        .on_read("\
+        .on_read_hook(&|w,e,buff| {
+            if w.has(e, READ) {
+                buff.puts("\
+...nine o'clock tomorrow morning...
+||The rest of the ink has faded away to nothing.  Good thing you read it
+while you could.
+                ");
+            } else {
+                buff.puts("\
 If you ever wish to see your toy aisle alive again, put $10,000 dollars
 under the statue in the castle courtyard before nine o'clock tomorrow morning.
 ||   -- Your host.
 ||Well.  That's a bit alarming.  Where are you going to find $10,000 at this time of day?
-         ");
+                ");
+            }
+        });
+
+    // The ink fades once the note has been read.
+    wb.on(&ReadThing("note"))
+        .once_only()
+        .set_flag("note", READ);
 
     // You can't read the note if it's dirty.
     wb.allow(&ReadThing("note"))
@@ -168,7 +210,8 @@ Weren't you paying attention? Only the pure may touch this sword.
 The sword almost seems to leap into your hands.  As you marvel at it
 (and, really, there's something odd about it), the marble block dissolves
 into white mist and blows away.
-        ");
+        ")
+        .describe("sword");
 
     // Room: Mouth of Cave
     wb.room("cave-mouth", "The Mouth of a Forbidding Cave")
@@ -209,6 +252,11 @@ and get that sword.
 It's an unpleasant place but your sword gives you confidence and warm fuzzies.
         ");
 
+    // The sword's too precious to leave lying in the cave.
+    wb.allow(&DropThing("sword"))
+        .unless(&|w| w.player_loc() == "cave-1")
+        .print("You'd better hold onto that.");
+
     // If the player dies, the fairy godmother revives him.
     wb.rule("fairy-godmother-rule")
         .when(&|w| w.has(PLAYER, Dead))
@@ -219,6 +267,18 @@ her wand.  There's a flash, and she disappears.
         ")
         .revive(PLAYER);
 
+    // NEXT, add hints.  The first relevant hint is shown.
+    wb.hint_for(&|w| w.has("hands", DIRTY), &[
+        "Your hands are awfully dirty.",
+        "The stone on the hilltop says, \"Only The Pure\".",
+        "Try washing your hands in the pool in the grotto.",
+    ]);
+
+    wb.hint_for(&|w| !w.owns(PLAYER, "sword"), &[
+        "The cave looks dangerous.  You could use a weapon.",
+        "Have you tried getting the sword on the hilltop?",
+    ]);
+
     // NEXT, add custom commands.
     // NOTE: Order is important!
 
@@ -227,14 +287,11 @@ her wand.  There's a flash, and she disappears.
             return Err("That'd be a neat trick, since there's no water here.".into());
         }
 
-        // TODO: Provide actions that build up paragraphs?
         let mut buff = ProseBuffer::new();
         buff.puts("You wash your hands in the water.");
-        if w.has("hands", DIRTY) {
-            buff.puts("They look much cleaner now.");
-        }
+        buff.puts_if(w.has("hands", DIRTY), "They look much cleaner now.");
 
-        script.print(&buff.get());
+        script.print_buffer(&buff);
         script.unset_flag("hands", DIRTY);
 
         Ok(())
@@ -245,7 +302,124 @@ her wand.  There's a flash, and she disappears.
         Ok(())
     });
 
+    wb.easter_egg("xyzzy", "A hollow voice says 'Fool.'");
 
     // NEXT, return the world.
     wb.world()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phys;
+    use crate::rule;
+    use crate::types::Event;
+    use crate::types::ProseType;
+    use crate::visual;
+    use crate::visual::OutputEvent;
+    use crate::world::WorldQuery;
+    use crate::world::LIMBO;
+
+    #[test]
+    fn note_fades_after_reading() {
+        let mut world = build();
+        let pid = world.pid;
+        let note = world.lookup("note");
+
+        assert!(visual::get_prose(&world, note, ProseType::Book).contains("toy aisle"));
+
+        phys::read_thing(&mut world, pid, note).unwrap();
+        assert!(world.has("note", READ));
+        assert!(visual::get_prose(&world, note, ProseType::Book).contains("faded"));
+    }
+
+    #[test]
+    fn dirty_note_cannot_be_read() {
+        let mut world = build();
+        let pid = world.pid;
+        let note = world.lookup("note");
+        world.set_flag(note, DIRTY);
+
+        // The guard prevents the read, so the ReadThing rule doesn't fire.
+        phys::read_thing(&mut world, pid, note).unwrap();
+        assert!(!world.has("note", READ));
+    }
+
+    #[test]
+    fn sword_with_clean_hands() {
+        let mut world = build();
+        let pid = world.pid;
+        let sword = world.lookup("sword");
+        world.unset_flag(world.lookup("hands"), DIRTY);
+
+        phys::get_thing(&mut world, pid, sword).unwrap();
+        assert!(world.owns(PLAYER, "sword"));
+        assert!(world.has("sword", TAKEN));
+        assert_eq!(phys::loc(&world, world.lookup("stone")), LIMBO);
+    }
+
+    #[test]
+    fn sword_with_dirty_hands() {
+        let mut world = build();
+        let pid = world.pid;
+        let sword = world.lookup("sword");
+
+        // The guard prints its warning, and kills the player.
+        let events = visual::capture(|| phys::get_thing(&mut world, pid, sword).unwrap());
+        assert!(matches!(&events[0], OutputEvent::Info(text) if text.contains("Only the pure")));
+        assert!(!world.owns(PLAYER, "sword"));
+        assert!(world.has(PLAYER, Dead));
+
+        // The fairy godmother revives the player on the next turn.
+        rule::fire_event(&mut world, &Event::Turn);
+        assert!(!world.has(PLAYER, Dead));
+    }
+
+    #[test]
+    fn sword_cannot_be_dropped_in_cave() {
+        let mut world = build();
+        let pid = world.pid;
+        let sword = world.lookup("sword");
+        let cave = world.lookup("cave-1");
+        let mouth = world.lookup("cave-mouth");
+        phys::put_in(&mut world, sword, pid);
+        phys::put_in(&mut world, pid, cave);
+
+        let events = visual::capture(|| phys::drop_thing(&mut world, pid, sword).unwrap());
+        assert_eq!(events, vec![OutputEvent::Info("You'd better hold onto that.".into())]);
+        assert!(world.owns(PLAYER, "sword"));
+
+        phys::put_in(&mut world, pid, mouth);
+        visual::capture(|| phys::drop_thing(&mut world, pid, sword).unwrap());
+        assert!(!world.owns(PLAYER, "sword"));
+        assert!(world.here("sword"));
+    }
+
+    #[test]
+    fn wash_hands() {
+        let mut game = crate::Game::default();
+        assert!(game.world.verbs.contains("wash"));
+
+        // There's no water in the clearing.
+        let events = visual::capture(|| game.turn("wash hands"));
+        assert!(matches!(&events[0], OutputEvent::Error(text) if text.contains("no water")));
+        assert!(game.world.has("hands", DIRTY));
+
+        visual::capture(|| {
+            game.turn("east");
+            game.turn("wash hands");
+        });
+        assert!(!game.world.has("hands", DIRTY));
+    }
+
+    #[test]
+    fn scenario_flags_resolve() {
+        // The scenario's flags are the canonical types::Flag.
+        let flags: [Flag; 4] = [DIRTY, HAS_WATER, TAKEN, READ];
+        assert_eq!(flags[0], Flag::User("DIRTY"));
+
+        let world = build();
+        assert!(world.has("hands", DIRTY));
+        assert!(world.has("sword", Scenery));
+    }
+}
@@ -1,17 +1,31 @@
 //! Scripts that mutate the world
 
+use crate::entity::ID;
 use crate::phys;
+use crate::rule;
 use self::Action::*;
+use crate::types::Event;
 use crate::types::Flag;
+use crate::types::ProseBuffer;
 use crate::visual;
 use crate::world::World;
 use crate::world_builder;
+use crate::world_builder::THIS;
+
+/// Looks up the tagged entity; THIS is the owner of the rule being executed.
+fn lookup(world: &World, tag: &str) -> ID {
+    if tag == THIS {
+        rule::owner().unwrap_or_else(|| panic!("{} used in a rule with no owner", THIS))
+    } else {
+        world.lookup(tag)
+    }
+}
 
 /// Actions taken by rules (and maybe other things)
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 enum Action {
-    /// Print the entity's visual
+    /// Print the text, which may contain conmark markup.
     Print(String),
 
     /// SetFlag(tag,flag): Set the flag on the tagged entity
@@ -35,6 +49,24 @@ enum Action {
 
     /// Revive(player): Revive the tagged player/NPC (currently, only the player)
     Revive(String),
+
+    /// AddScore(points, announce): Add points to the player's score, announcing
+    /// the change if requested and the player hasn't turned notifications off.
+    AddScore(u32, bool),
+
+    /// Describe(tag): Print the tagged entity's description, as though the player
+    /// had looked at it.
+    Describe(String),
+
+    /// FireEvent(event): Fire the event, triggering any rules that respond to it.
+    FireEvent(Event),
+
+    /// FireCustom(name, tag): Fire the named custom event for the tagged entity.
+    FireCustom(&'static str, String),
+
+    /// IfFlag(tag, flag, then, otherwise): If the tagged entity has the flag, execute
+    /// the "then" script; otherwise, execute the "otherwise" script.
+    IfFlag(String, Flag, Script, Script),
 }
 
 /// A script of actions for execution.  Scripts can be pre-defined and executed
@@ -59,6 +91,20 @@ impl Script {
         }
     }
 
+    /// Does the script refer to the tagged entity?
+    pub fn mentions(&self, world: &World, tag: &str) -> bool {
+        self.actions.iter().any(|action| match action {
+            Print(_) | AddScore(_, _) => false,
+            SetFlag(t, _) | UnsetFlag(t, _) | Kill(t) | Revive(t) | Describe(t) => t == tag,
+            PutIn(a, b) | Swap(a, b) | Drop(a, b) => a == tag || b == tag,
+            FireEvent(event) => world.lookup_id(tag).is_some_and(|id| event.ids().contains(&id)),
+            FireCustom(_, t) => t == tag,
+            IfFlag(t, _, then, otherwise) => {
+                t == tag || then.mentions(world, tag) || otherwise.mentions(world, tag)
+            }
+        })
+    }
+
     /// Executes a script on the world.
     pub fn execute(&self, world: &mut World) {
         for action in &self.actions {
@@ -70,29 +116,29 @@ impl Script {
 
                 // Set the flag on the entity's flag set
                 SetFlag(tag, flag) => {
-                    world.set_flag(world.lookup(tag), *flag);
+                    world.set_flag(lookup(world, tag), *flag);
                 }
 
                 // Clear the flag on the entity's flag set
                 UnsetFlag(tag, flag) => {
-                    world.unset_flag(world.lookup(tag), *flag);
+                    world.unset_flag(lookup(world, tag), *flag);
                 }
 
                 // Moves a thing to a given place.
                 PutIn(thing, inv) => {
-                    phys::put_in(world, world.lookup(thing), world.lookup(inv));
+                    phys::put_in(world, lookup(world, thing), lookup(world, inv));
                 }
 
                 // Player/NPC drops thing into its current location.
                 Drop(player, thing) => {
-                    let loc = phys::loc(world, world.lookup(player));
-                    phys::put_in(world, world.lookup(thing), loc);
+                    let loc = phys::loc(world, lookup(world, player));
+                    phys::put_in(world, lookup(world, thing), loc);
                 }
 
                 // Swap a, in a place, with b, in LIMBO
                 Swap(a, b) => {
-                    let aid = world.lookup(a);
-                    let bid = world.lookup(b);
+                    let aid = lookup(world, a);
+                    let bid = lookup(world, b);
                     let loc = phys::loc(world, aid);
                     phys::take_out(world, aid);
                     phys::put_in(world, bid, loc);
@@ -100,15 +146,58 @@ impl Script {
 
                 // Kill the player/NPC
                 Kill(player) => {
-                    world.set_flag(world.lookup(player), Flag::Dead);
+                    world.set_flag(lookup(world, player), Flag::Dead);
                     visual::act("*** You have died. ***");
                 }
 
                 // Revive the player/NPC
                 Revive(player) => {
-                    world.unset_flag(world.lookup(player), Flag::Dead);
+                    world.unset_flag(lookup(world, player), Flag::Dead);
                     visual::act("*** You are alive! ***");
                 }
+
+                // Add to the score
+                AddScore(points, announce) => {
+                    world.score += points;
+                    if *announce && world.notify {
+                        let noun = if *points == 1 { "point" } else { "points" };
+                        visual::info(&format!(
+                            "[Your score just went up by {} {}.]",
+                            points, noun
+                        ));
+                    }
+                }
+
+                // Describe the entity
+                Describe(tag) => {
+                    let id = lookup(world, tag);
+                    if world.is_room(id) {
+                        visual::room(world, id);
+                    } else if world.is_player(id) {
+                        visual::player(world, id);
+                    } else {
+                        visual::thing(world, id);
+                    }
+                }
+
+                // Fire the event, triggering other rules
+                FireEvent(event) => {
+                    rule::fire_event(world, event);
+                }
+
+                // Fire the custom event
+                FireCustom(name, tag) => {
+                    rule::fire_event(world, &Event::Custom(name, lookup(world, tag)));
+                }
+
+                // Execute one script or the other, depending on the flag.
+                IfFlag(tag, flag, then, otherwise) => {
+                    if world.has_flag(lookup(world, tag), *flag) {
+                        then.execute(world);
+                    } else {
+                        otherwise.execute(world);
+                    }
+                }
             }
         }
     }
@@ -127,6 +216,12 @@ impl Script {
         self.add(Print(text.into()));
     }
 
+    /// Adds an action to print the contents of the prose buffer, e.g., text
+    /// composed conditionally by a hook.
+    pub fn print_buffer(&mut self, buff: &ProseBuffer) {
+        self.add(Print(buff.get()));
+    }
+
     /// Adds an action to set the given flag on the tagged entity.
     pub fn set_flag(&mut self, tag: &str, flag: Flag) {
         self.add(SetFlag(tag.into(), flag));
@@ -153,4 +248,130 @@ impl Script {
     pub fn revive(&mut self, player: &str) {
         self.add(Action::Revive(player.into()));
     }
+
+    /// Adds an action to fire the event, triggering any rules that respond to it.
+    pub fn fire_event(&mut self, event: Event) {
+        self.add(FireEvent(event));
+    }
+
+    /// Adds an action to fire the named custom event for the tagged entity, e.g.,
+    /// `script.fire_custom("ritual_complete", "altar")`.
+    pub fn fire_custom(&mut self, name: &'static str, tag: &str) {
+        self.add(FireCustom(name, tag.into()));
+    }
+
+    /// Adds an action to add points to the player's score, with a notification.
+    pub fn add_score(&mut self, points: u32) {
+        self.add(AddScore(points, true));
+    }
+
+    /// Adds an action to add points to the player's score without a notification.
+    pub fn add_score_quietly(&mut self, points: u32) {
+        self.add(AddScore(points, false));
+    }
+
+    /// Adds an action to print the tagged entity's description.
+    pub fn describe(&mut self, tag: &str) {
+        self.add(Describe(tag.into()));
+    }
+
+    /// Adds an action that executes the actions added by the closure only if the tagged
+    /// entity has the given flag at run time.
+    pub fn when_flag(&mut self, tag: &str, flag: Flag, then: &dyn Fn(&mut Script)) {
+        self.when_flag_else(tag, flag, then, &|_| {});
+    }
+
+    /// Adds an action that executes the actions added by the `then` closure if the tagged
+    /// entity has the given flag at run time, and those added by the `otherwise` closure
+    /// if it does not.
+    pub fn when_flag_else(
+        &mut self,
+        tag: &str,
+        flag: Flag,
+        then: &dyn Fn(&mut Script),
+        otherwise: &dyn Fn(&mut Script),
+    ) {
+        let mut then_script = Script::new();
+        then(&mut then_script);
+        let mut otherwise_script = Script::new();
+        otherwise(&mut otherwise_script);
+
+        self.add(IfFlag(tag.into(), flag, then_script, otherwise_script));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Flag::User;
+    use crate::world_builder::WorldBuilder;
+
+    #[test]
+    fn when_flag_else() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("door", "door", "door").location("here").flag(User("LOCKED"));
+        let mut world = wb.world();
+        let door = world.lookup("door");
+
+        let mut script = Script::new();
+        script.when_flag_else(
+            "door",
+            User("LOCKED"),
+            &|s| s.unset_flag("door", User("LOCKED")),
+            &|s| s.set_flag("door", User("OPEN")),
+        );
+
+        // The door is locked, so it gets unlocked.
+        script.execute(&mut world);
+        assert!(!world.has_flag(door, User("LOCKED")));
+        assert!(!world.has_flag(door, User("OPEN")));
+
+        // The door is unlocked, so it gets opened.
+        script.execute(&mut world);
+        assert!(world.has_flag(door, User("OPEN")));
+    }
+
+    #[test]
+    fn add_score() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        let mut world = wb.world();
+
+        let mut script = Script::new();
+        script.add_score(5);
+        script.add_score_quietly(1);
+
+        let events = visual::capture(|| script.execute(&mut world));
+        assert_eq!(
+            events,
+            vec![visual::OutputEvent::Info("[Your score just went up by 5 points.]".into())]
+        );
+        assert_eq!(world.score, 6);
+
+        world.notify = false;
+        let events = visual::capture(|| script.execute(&mut world));
+        assert!(events.is_empty());
+        assert_eq!(world.score, 12);
+    }
+
+    #[test]
+    fn mentions() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("door", "door", "door").location("here");
+        wb.thing("key", "key", "key").location("here");
+        let world = wb.world();
+
+        let mut script = Script::new();
+        script.print("Creak.");
+        script.when_flag("door", User("LOCKED"), &|s| s.forget("key"));
+
+        assert!(script.mentions(&world, "door"));
+        assert!(script.mentions(&world, "key"));
+        assert!(!script.mentions(&world, "here"));
+    }
 }
@@ -27,8 +27,7 @@ pub type CommandHook = &'static Fn(&WorldQuery, &[&str], &mut Script) -> Command
 pub type Time = usize;
 
 /// Directions
-#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
-#[allow(dead_code)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
 pub enum Dir {
     North,
     South,
@@ -40,6 +39,143 @@ pub enum Dir {
     Out,
 }
 
+impl Dir {
+    /// All of the directions, in order.
+    pub const ALL: [Dir; 8] = [
+        Dir::North,
+        Dir::South,
+        Dir::East,
+        Dir::West,
+        Dir::Up,
+        Dir::Down,
+        Dir::In,
+        Dir::Out,
+    ];
+
+    /// Parses a direction from its name or abbreviation, e.g., "north" or "n",
+    /// ignoring case.
+    pub fn from_str(word: &str) -> Option<Dir> {
+        match word.to_lowercase().as_str() {
+            "north" | "n" => Some(Dir::North),
+            "south" | "s" => Some(Dir::South),
+            "east" | "e" => Some(Dir::East),
+            "west" | "w" => Some(Dir::West),
+            "up" | "u" => Some(Dir::Up),
+            "down" | "d" => Some(Dir::Down),
+            "in" => Some(Dir::In),
+            "out" => Some(Dir::Out),
+            _ => None,
+        }
+    }
+
+    /// Returns the direction's name, for display, e.g., "north".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dir::North => "north",
+            Dir::South => "south",
+            Dir::East => "east",
+            Dir::West => "west",
+            Dir::Up => "up",
+            Dir::Down => "down",
+            Dir::In => "in",
+            Dir::Out => "out",
+        }
+    }
+
+    /// Returns the opposite direction, e.g., South for North.
+    pub fn opposite(self) -> Dir {
+        match self {
+            Dir::North => Dir::South,
+            Dir::South => Dir::North,
+            Dir::East => Dir::West,
+            Dir::West => Dir::East,
+            Dir::Up => Dir::Down,
+            Dir::Down => Dir::Up,
+            Dir::In => Dir::Out,
+            Dir::Out => Dir::In,
+        }
+    }
+}
+
+/// Ways of manipulating a thing, e.g., a lever or a button.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Manipulation {
+    Push,
+    Pull,
+    Turn,
+    Press,
+}
+
+impl Manipulation {
+    /// All of the manipulations.
+    pub const ALL: [Manipulation; 4] = [
+        Manipulation::Push,
+        Manipulation::Pull,
+        Manipulation::Turn,
+        Manipulation::Press,
+    ];
+
+    /// Returns the manipulation's verb, e.g., "push".
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Manipulation::Push => "push",
+            Manipulation::Pull => "pull",
+            Manipulation::Turn => "turn",
+            Manipulation::Press => "press",
+        }
+    }
+
+    /// Parses a manipulation from its verb.
+    pub fn from_verb(verb: &str) -> Option<Manipulation> {
+        Manipulation::ALL.iter().find(|m| m.verb() == verb).cloned()
+    }
+
+    /// The response when manipulating a thing has no effect.
+    pub fn default_response(&self) -> &'static str {
+        match self {
+            Manipulation::Push | Manipulation::Pull => "It won't move.",
+            Manipulation::Turn => "It won't turn.",
+            Manipulation::Press => "Nothing happens.",
+        }
+    }
+}
+
+/// The order in which things are listed, e.g., in the player's inventory.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[allow(dead_code)]
+pub enum ListOrder {
+    /// Things are listed in the order in which they were created.
+    #[default]
+    CreationOrder,
+
+    /// Things are listed alphabetically by name.
+    Alphabetical,
+}
+
+/// A progressive hint: a list of hints, from vague to specific, that is relevant
+/// while its predicate is true.
+#[derive(Clone)]
+pub struct Hint {
+    /// Is the hint relevant to the current state of the world?
+    pub predicate: RulePredicate,
+
+    /// The hint texts, from vague to specific
+    pub texts: Vec<String>,
+
+    /// The number of texts shown so far
+    pub shown: usize,
+}
+
+impl Hint {
+    /// Returns the next hint text to show, advancing through the list.  Once all of
+    /// the texts have been shown, the last one is repeated.
+    pub fn next(&mut self) -> String {
+        let text = self.texts[self.shown.min(self.texts.len() - 1)].clone();
+        self.shown = (self.shown + 1).min(self.texts.len());
+        text
+    }
+}
+
 /// The different kinds of prose supported by an entity.
 #[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
 pub enum ProseType {
@@ -54,6 +190,24 @@ pub enum ProseType {
 
     /// The prose description of a thing as scenery in a container
     Scenery,
+
+    /// How an entity smells
+    Smell,
+
+    /// How an entity sounds
+    Sound,
+
+    /// How an entity feels to the touch
+    Touch,
+
+    /// A thing's initial mention in a room description, e.g., "A crumpled note
+    /// lies on the ground."  Used in place of the object list until the thing
+    /// has been handled.
+    Initial,
+
+    /// Dynamic text appended to a room's full description, e.g., "A cold wind
+    /// blows through." at night.  May be empty.
+    Ambience,
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
@@ -83,6 +237,30 @@ pub enum Flag {
     /// should also be Immovable.
     Scenery,
 
+    /// Is the thing bound to its holder?  I.e., something that the holder
+    /// carries but can't drop or give away, e.g., a cursed sword.
+    Bound,
+
+    /// Is the room dark?  A dark room can't be seen in without a light source.
+    Dark,
+
+    /// Does the thing give light?  A light source lights a dark room when it's in
+    /// the room or carried by someone in the room.
+    LightSource,
+
+    /// Is the container closed?  Its contents can't be seen or reached.
+    Closed,
+
+    /// Is the thing locked?  A locked thing can't be opened, or read.
+    Locked,
+
+    /// Is the thing a character, i.e., an NPC that notices the player?
+    Npc,
+
+    /// Has the player handled the thing, i.e., gotten or examined it?  Things
+    /// with Initial prose are described by it until they've been handled.
+    Handled,
+
     /// A generic flag type for use by users
     User(&'static str),
 
@@ -99,12 +277,59 @@ pub enum Event {
     /// EnterRoom(player, room): A player has entered (or wants to enter) a room
     EnterRoom(ID, ID),
 
+    /// LeaveRoom(player, room): A player is leaving (or wants to leave) a room
+    LeaveRoom(ID, ID),
+
     /// GetThing(player, thing): A player has gotten (or wants to get) a thing
     GetThing(ID, ID),
 
+    /// DropThing(player, thing): A player has dropped (or wants to drop) a thing
+    DropThing(ID, ID),
+
     /// ReadThing(player, thing): A player has read (or wants to read) a thing's
     /// Book prose.
     ReadThing(ID, ID),
+
+    /// GiveThing(player, thing, recipient): A player has given (or wants to give)
+    /// a thing to a recipient, e.g., an NPC.
+    GiveThing(ID, ID, ID),
+
+    /// TakeFrom(player, thing, container): A player has taken (or wants to take)
+    /// a thing out of a container.
+    TakeFrom(ID, ID, ID),
+
+    /// NpcSees(npc, player): An NPC sees a player enter its room.
+    NpcSees(ID, ID),
+
+    /// UseOn(player, tool, target): A player uses (or wants to use) a tool on a target,
+    /// e.g., a key on a door.
+    UseOn(ID, ID, ID),
+
+    /// Manipulate(player, thing, manipulation): A player pushes, pulls, etc., (or
+    /// wants to) a thing.
+    Manipulate(ID, ID, Manipulation),
+
+    /// Custom(name, entity): A scenario-specific event concerning an entity, e.g.,
+    /// Custom("ritual_complete", altar).  Scenarios fire these from hooks and rules.
+    Custom(&'static str, ID),
+}
+
+impl Event {
+    /// Returns the IDs of the entities involved in the event.
+    pub fn ids(&self) -> Vec<ID> {
+        match self {
+            Event::Turn => vec![],
+            Event::EnterRoom(a, b)
+            | Event::LeaveRoom(a, b)
+            | Event::GetThing(a, b)
+            | Event::DropThing(a, b)
+            | Event::ReadThing(a, b)
+            | Event::NpcSees(a, b)
+            | Event::Manipulate(a, b, _) => vec![*a, *b],
+            Event::GiveThing(a, b, c) | Event::TakeFrom(a, b, c) | Event::UseOn(a, b, c) => vec![*a, *b, *c],
+            Event::Custom(_, a) => vec![*a],
+        }
+    }
 }
 
 /// The destination of a link.
@@ -113,9 +338,10 @@ pub enum LinkDest {
     /// The link goes to another room.
     Room(ID),
 
-    /// The link is a dead end.  The string is the prose to display to
-    /// the user.
-    DeadEnd(String)
+    /// DeadEnd(prose, advertised): The link is a dead end.  The string is the prose
+    /// to display to the user.  If advertised is true, the dead end is listed as
+    /// an apparent exit; otherwise it's hidden.
+    DeadEnd(String, bool),
 }
 
 /// ProseBuffer: A buffer for building up strings of prose.
@@ -129,6 +355,10 @@ pub enum LinkDest {
 #[derive(Default)]
 pub struct ProseBuffer {
     buff: String,
+
+    /// If set, puts() begins a new paragraph once the current paragraph has
+    /// at least this many words.
+    para_limit: Option<usize>,
 }
 
 impl ProseBuffer {
@@ -136,16 +366,46 @@ impl ProseBuffer {
     pub fn new() -> Self {
         Self {
             buff: String::new(),
+            para_limit: None,
         }
     }
 
-    /// Adds a sentence to the buffer, separating it from previous text
-    /// with a blank space if necessary.
+    /// Sets the paragraph limit: once the current paragraph has at least this many
+    /// words, puts() will begin a new paragraph rather than extending the current one.
+    #[allow(dead_code)]
+    pub fn set_para_limit(&mut self, words: usize) {
+        self.para_limit = Some(words);
+    }
+
+    /// Adds a sentence to the buffer, separating it from previous text with white
+    /// space if necessary.  The white space is a newline, which conmark renders as
+    /// a single blank; thus, successive sentences flow together into one paragraph,
+    /// and the buffer remains readable when dumped.  Use newline() or para() to
+    /// break the text explicitly.
+    ///
+    /// If a paragraph limit has been set and the current paragraph has reached it,
+    /// the sentence begins a new paragraph.
     pub fn puts(&mut self, text: &str) {
+        if let Some(limit) = self.para_limit {
+            self.para_if_long(limit);
+        }
         self.add_white_space_if_needed();
         self.put_raw(text);
     }
 
+    /// Adds a sentence to the buffer if the condition is true.
+    pub fn puts_if(&mut self, cond: bool, text: &str) {
+        if cond {
+            self.puts(text);
+        }
+    }
+
+    /// Adds one sentence or the other to the buffer, depending on the condition.
+    pub fn puts_or(&mut self, cond: bool, text: &str, otherwise: &str) {
+        self.puts(if cond { text } else { otherwise });
+    }
+
+    /// Adds a line break to the buffer.
     pub fn newline(&mut self) {
         self.buff.push_str("|");
     }
@@ -156,6 +416,33 @@ impl ProseBuffer {
         self.buff.push_str("||");
     }
 
+    /// Adds a paragraph break to the buffer if the current paragraph has at
+    /// least the given number of words.
+    pub fn para_if_long(&mut self, words: usize) {
+        let count = self.para_words();
+        if count > 0 && count >= words {
+            self.para();
+        }
+    }
+
+    /// Counts the words in the current paragraph.
+    fn para_words(&self) -> usize {
+        self.buff.rsplit("||").next().unwrap_or("").split_whitespace().count()
+    }
+
+    /// Adds a bulleted list item to the buffer, on a line of its own.
+    pub fn bullet(&mut self, text: &str) {
+        self.put_raw("|-");
+        self.put_raw(text.trim());
+    }
+
+    /// Adds a numbered list to the buffer, one item per line.
+    pub fn numbered(&mut self, items: &[&str]) {
+        for (i, item) in items.iter().enumerate() {
+            self.put_raw(&format!("|  {}. {}", i + 1, item.trim()));
+        }
+    }
+
     /// Adds text to the buffer, with no special handling.
     pub fn put_raw(&mut self, text: &str) {
         self.buff.push_str(text);
@@ -178,3 +465,114 @@ impl ProseBuffer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dir_from_str() {
+        for dir in &Dir::ALL {
+            assert_eq!(Dir::from_str(dir.name()), Some(*dir));
+        }
+
+        assert_eq!(Dir::from_str("n"), Some(Dir::North));
+        assert_eq!(Dir::from_str("N"), Some(Dir::North));
+        assert_eq!(Dir::from_str("d"), Some(Dir::Down));
+        assert_eq!(Dir::from_str("sideways"), None);
+    }
+
+    #[test]
+    fn manipulation_from_verb() {
+        for how in &Manipulation::ALL {
+            assert_eq!(Manipulation::from_verb(how.verb()), Some(*how));
+        }
+
+        assert_eq!(Manipulation::from_verb("poke"), None);
+    }
+
+    #[test]
+    fn dir_opposite() {
+        assert_eq!(Dir::North.opposite(), Dir::South);
+        assert_eq!(Dir::West.opposite(), Dir::East);
+        assert_eq!(Dir::Up.opposite(), Dir::Down);
+        assert_eq!(Dir::Out.opposite(), Dir::In);
+    }
+
+    #[test]
+    fn hint_next() {
+        let mut hint = Hint {
+            predicate: &|_| true,
+            texts: vec!["vague".into(), "specific".into()],
+            shown: 0,
+        };
+
+        assert_eq!(hint.next(), "vague");
+        assert_eq!(hint.next(), "specific");
+        assert_eq!(hint.next(), "specific");
+    }
+
+    #[test]
+    fn prose_buffer_puts() {
+        let mut buff = ProseBuffer::new();
+        buff.puts("One.");
+        buff.puts("Two.");
+        assert_eq!(buff.get(), "One.\nTwo.");
+    }
+
+    #[test]
+    fn prose_buffer_puts_if() {
+        let mut buff = ProseBuffer::new();
+        buff.puts_if(true, "One.");
+        buff.puts_if(false, "Two.");
+        buff.puts_if(true, "Three.");
+        assert_eq!(buff.get(), "One.\nThree.");
+    }
+
+    #[test]
+    fn prose_buffer_puts_or() {
+        let mut buff = ProseBuffer::new();
+        buff.puts_or(true, "One.", "Uno.");
+        buff.puts_or(false, "Two.", "Dos.");
+        assert_eq!(buff.get(), "One.\nDos.");
+    }
+
+    #[test]
+    fn prose_buffer_para_if_long() {
+        let mut buff = ProseBuffer::new();
+        buff.para_if_long(2);
+        buff.puts("One two.");
+        buff.para_if_long(3);
+        buff.puts("Three.");
+        buff.para_if_long(3);
+        buff.puts("Four.");
+        assert_eq!(buff.get(), "One two.\nThree.||Four.");
+    }
+
+    #[test]
+    fn prose_buffer_para_limit() {
+        let mut buff = ProseBuffer::new();
+        buff.set_para_limit(2);
+        buff.puts("One.");
+        buff.puts("Two.");
+        buff.puts("Three.");
+        assert_eq!(buff.get(), "One.\nTwo.||Three.");
+    }
+
+    #[test]
+    fn prose_buffer_bullet() {
+        let mut buff = ProseBuffer::new();
+        buff.puts("Items:");
+        buff.bullet("sword");
+        buff.bullet("note");
+        assert_eq!(buff.get(), "Items:|-sword|-note");
+    }
+
+    #[test]
+    fn prose_buffer_numbered() {
+        let mut buff = ProseBuffer::new();
+        buff.puts("Steps:");
+        buff.numbered(&["wash", "get sword"]);
+        assert_eq!(buff.get(), "Steps:|  1. wash|  2. get sword");
+    }
+}
@@ -8,14 +8,21 @@
 // physics system to render the current scene.  In a text adventure, text is displayed at
 // appropriate moments in processing; thus, this module is called as needed, rather than
 // doing its work all at once.
+//
+// The visuals are not printed directly; rather, they are emitted as OutputEvents to the
+// current OutputSink.  By default this is the console::ConsoleSink, but a GUI or web
+// frontend can provide its own.
 
-use crate::console::para;
+use crate::console::ConsoleSink;
 use crate::entity::ID;
 use crate::phys;
+use crate::types::Dir;
+use crate::types::Flag;
+use crate::types::LinkDest;
 use crate::types::ProseType;
 use crate::types::ProseBuffer;
 use crate::world::World;
-use std::collections::BTreeSet;
+use std::cell::RefCell;
 
 //-----------------------------------------------------------------------------
 // Types
@@ -27,30 +34,150 @@ enum Detail {
     Brief,
 }
 
+/// The structured output of the visual system.  Strings use conmark syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputEvent {
+    /// Room(name, description): A room's name, and its full description, if any
+    Room(String, Option<String>),
+
+    /// The names of the things visible in a room
+    ObjectList(Vec<String>),
+
+    /// Inventory(carried, bound): The names of the things in the player's inventory:
+    /// those carried normally, and those bound to the player, which can't be dropped.
+    Inventory(Vec<String>, Vec<String>),
+
+    /// Descriptive prose, e.g., a thing's description
+    Body(String),
+
+    /// The result of a player action, e.g., "Taken."
+    Action(String),
+
+    /// An error message
+    Error(String),
+
+    /// Information, e.g., help
+    Info(String),
+}
+
+/// A receiver of OutputEvents, i.e., a frontend.
+pub trait OutputSink {
+    /// Displays the event to the user.
+    fn emit(&mut self, event: OutputEvent);
+}
+
+thread_local! {
+    /// The current output sink.
+    static SINK: RefCell<Box<dyn OutputSink>> = RefCell::new(Box::new(ConsoleSink));
+}
+
+/// Replaces the current output sink, returning the previous one.
+#[allow(dead_code)]
+pub fn set_sink(sink: Box<dyn OutputSink>) -> Box<dyn OutputSink> {
+    SINK.with(|cell| std::mem::replace(&mut *cell.borrow_mut(), sink))
+}
+
+/// Emits an event to the current output sink.
+fn emit(event: OutputEvent) {
+    SINK.with(|cell| cell.borrow_mut().emit(event));
+}
+
+/// Executes the closure, capturing and returning the events it emits rather than
+/// displaying them.  The previous sink is restored afterwards, even if the closure
+/// panics.
+#[cfg(test)]
+pub fn capture(f: impl FnOnce()) -> Vec<OutputEvent> {
+    use std::rc::Rc;
+
+    struct CaptureSink(Rc<RefCell<Vec<OutputEvent>>>);
+
+    impl OutputSink for CaptureSink {
+        fn emit(&mut self, event: OutputEvent) {
+            self.0.borrow_mut().push(event);
+        }
+    }
+
+    // Restores the previous sink when dropped.
+    struct Restore(Option<Box<dyn OutputSink>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(sink) = self.0.take() {
+                set_sink(sink);
+            }
+        }
+    }
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let restore = Restore(Some(set_sink(Box::new(CaptureSink(events.clone())))));
+    f();
+    drop(restore);
+
+    events.take()
+}
+
 //-----------------------------------------------------------------------------
 // Basic Messages
-//
-// At present these are all treated like "para"; but this gives the opportunity
-// to distinguish them at some future time.
 
 /// Outputs a player action, e.g., "Taken."
 pub fn act(msg: &str) {
-    para(msg);
+    emit(OutputEvent::Action(msg.into()));
 }
 
 /// Outputs an error message.
 pub fn error(msg: &str) {
-    para(msg);
+    emit(OutputEvent::Error(msg.into()));
 }
 
 /// Outputs information (e.g., help)
 pub fn info(msg: &str) {
-    para(msg);
+    emit(OutputEvent::Info(msg.into()));
+}
+
+/// Begins a prose message, e.g., a command's response, built up from sentences:
+///
+/// ```ignore
+/// visual::prose("You wash your hands.")
+///     .when(dirty, "They look much cleaner now.")
+///     .para();
+/// ```
+pub fn prose(text: &str) -> Prose {
+    let mut buff = ProseBuffer::new();
+    buff.puts(text);
+    Prose { buff }
+}
+
+/// A prose message under construction; see `prose()`.
+pub struct Prose {
+    buff: ProseBuffer,
+}
+
+impl Prose {
+    /// Adds a sentence.
+    #[allow(dead_code)]
+    pub fn puts(mut self, text: &str) -> Self {
+        self.buff.puts(text);
+        self
+    }
+
+    /// Adds a sentence if the condition is true.
+    pub fn when(mut self, cond: bool, text: &str) -> Self {
+        self.buff.puts_if(cond, text);
+        self
+    }
+
+    /// Outputs the message as a paragraph of information.
+    pub fn para(self) {
+        info(&self.buff.get());
+    }
 }
 
 //-----------------------------------------------------------------------------
 // Room Visuals
 
+/// The description of a room that's too dark to see.
+const DARKNESS: &str = "It's pitch black.  You can't see a thing.";
+
 /// Outputs a full description of a room.
 ///
 /// A full description includes the room's name, visual, and any things that are present.
@@ -73,28 +200,79 @@ pub fn room_brief(world: &World, id: ID) {
 fn print_room(world: &World, id: ID, detail: Detail) {
     let roomc = &world.rooms[&id];
 
-    // FIRST, display the room's description
+    // FIRST, in the dark there's nothing to see.
+    if !phys::is_lit(world, id) {
+        emit(OutputEvent::Room(roomc.name.clone(), Some(DARKNESS.into())));
+        return;
+    }
+
+    // NEXT, split the room's "removable" objects into those that have initial
+    // prose and those that are simply listed.  (We don't list scenery; presumably
+    // that's in the description.)
+    let (initial, listed): (Vec<_>, Vec<_>) = phys::named_contents(world, id)
+        .into_iter()
+        .partition(|(thing, _)| has_initial_prose(world, *thing));
+
+    let mut initial_buff = ProseBuffer::new();
+    for (thing, _) in &initial {
+        initial_buff.puts(&get_prose(world, *thing, ProseType::Initial));
+    }
+
+    // NEXT, display the room's description, followed by the initial prose.
     if detail == Detail::Full {
         let mut buff = ProseBuffer::new();
-        buff.puts(&roomc.name);
-        buff.newline();
         buff.puts(&get_prose(world, id, ProseType::Room));
-        for sid in phys::scenery(world, id) {
-            if world.has_prose_type(sid, ProseType::Scenery) {
-                buff.puts(&get_prose(world, sid, ProseType::Scenery));
+        put_scenery_prose(world, id, &mut buff);
+        if !initial.is_empty() {
+            buff.puts(&initial_buff.get());
+        }
+        if world.has_prose_type(id, ProseType::Ambience) {
+            let ambience = get_prose(world, id, ProseType::Ambience);
+            if !ambience.is_empty() {
+                buff.puts(&ambience);
             }
         }
-        para(&buff.get());
+        emit(OutputEvent::Room(roomc.name.clone(), Some(buff.get())));
     } else {
-        para(&roomc.name);
+        emit(OutputEvent::Room(roomc.name.clone(), None));
+
+        if !initial.is_empty() {
+            emit(OutputEvent::Body(initial_buff.get()));
+        }
     }
 
-    // NEXT, list any "removable" objects in the room's inventory.  (We don't list
-    // scenary; presumably that's in the description.)
-    let list = invent_list(world, &phys::non_scenery(world, id));
+    // NEXT, list the remaining objects.
+    let list = invent_list(world, &listed);
 
     if !list.is_empty() {
-        para!("You see: {}.", list);
+        emit(OutputEvent::ObjectList(list));
+    }
+}
+
+/// Is the thing described by its initial prose, i.e., does it have Initial prose
+/// and has it not yet been handled?
+fn has_initial_prose(world: &World, thing: ID) -> bool {
+    world.has_prose_type(thing, ProseType::Initial) && !world.has_flag(thing, Flag::Handled)
+}
+
+/// Outputs a list of the room's obvious exits, i.e., the directions that lead to
+/// other rooms.  Dead ends are omitted unless they are advertised as exits.
+pub fn exits(world: &World, id: ID) {
+    let roomc = &world.rooms[&id];
+
+    let mut dirs: Vec<Dir> = roomc
+        .links
+        .iter()
+        .filter(|(_, dest)| matches!(dest, LinkDest::Room(_) | LinkDest::DeadEnd(_, true)))
+        .map(|(dir, _)| *dir)
+        .collect();
+    dirs.sort();
+
+    if dirs.is_empty() {
+        info("There are no obvious exits.");
+    } else {
+        let names: Vec<&str> = dirs.iter().map(|dir| dir.name()).collect();
+        info(&format!("Obvious exits: {}.", names.join(", ")));
     }
 }
 
@@ -104,7 +282,7 @@ fn print_room(world: &World, id: ID, detail: Detail) {
 /// Outputs a description of a thing.
 pub fn thing(world: &World, id: ID) {
     // FIRST, display the thing's description
-    para(&get_prose(world, id, ProseType::Thing));
+    emit(OutputEvent::Body(get_prose(world, id, ProseType::Thing)));
 
     // TODO: eventually we will want to describe its contents, if it has
     // contents, or other changeable state.
@@ -125,6 +303,22 @@ pub fn read(world: &World, book: ID) {
     act(&buff.get());
 }
 
+/// Outputs how an entity smells, sounds, or feels, or a default response if it has
+/// no such prose.
+pub fn sense(world: &World, id: ID, prose_type: ProseType) {
+    if world.has_prose_type(id, prose_type) {
+        emit(OutputEvent::Body(get_prose(world, id, prose_type)));
+    } else {
+        let msg = match prose_type {
+            ProseType::Smell => "You smell nothing unusual.",
+            ProseType::Sound => "You hear nothing unusual.",
+            ProseType::Touch => "You feel nothing unusual.",
+            _ => "You don't notice anything special.",
+        };
+        info(msg);
+    }
+}
+
 //-----------------------------------------------------------------------------
 // Player Visuals
 
@@ -133,61 +327,251 @@ pub fn player(world: &World, pid: ID) {
     // FIRST, display the player's description
     let mut buff = ProseBuffer::new();
     buff.puts(&get_prose(world, pid, ProseType::Thing));
-    for sid in phys::scenery(world, pid) {
-        if world.has_prose_type(sid, ProseType::Scenery) {
-            let prose = &get_prose(world, sid, ProseType::Scenery);
-            // With a prose hook, result could be empty.
-            if !prose.is_empty() {
-                buff.puts(prose);
-            }
-        }
+    put_scenery_prose(world, pid, &mut buff);
+
+    // NEXT, add what the player is carrying, and any status conditions.
+    let carried = invent_list(world, &phys::named_contents(world, pid));
+    if !carried.is_empty() {
+        buff.puts(&format!("You are carrying: {}.", carried.join(", ")));
     }
-    para(&buff.get());
 
-    // TODO: Could add inventory.
+    if world.has_flag(pid, Flag::Dead) {
+        buff.puts("You are, regrettably, dead.");
+    }
+
+    emit(OutputEvent::Body(buff.get()));
 }
 
+/// A list of things with their names, as returned by `phys::named_contents`.
+type NamedThings = Vec<(ID, String)>;
+
 /// Outputs the player's inventory
 pub fn player_inventory(world: &World, pid: ID) {
     // A player's inventory is precisely the things that they are carrying that
-    // are (in theory at least) droppable: the player's sword, but not the player's hands.
-    let ids = phys::droppable(world, pid);
-
-    if ids.is_empty() {
-        para("You aren't carrying anything.");
-    } else {
-        para!("You have: {}.\n", invent_list(world, &ids));
-    }
+    // aren't part of the player: the player's sword, but not the player's hands.
+    // Things bound to the player are reported separately.
+    let (bound, carried): (NamedThings, NamedThings) = phys::named_contents(world, pid)
+        .into_iter()
+        .partition(|(id, _)| world.has_flag(*id, Flag::Bound));
+
+    emit(OutputEvent::Inventory(
+        invent_list(world, &carried),
+        invent_list(world, &bound),
+    ));
 }
 
-/// List the names of the entities, separated by commas.
-fn invent_list(world: &World, ids: &BTreeSet<ID>) -> String {
-    let mut list = String::new();
-
-    for id in ids {
-        let thingc = &world.things[&id];
-
-        if !list.is_empty() {
-            list.push_str(", ");
+/// List the names of the entities.  Things with the same name are listed together,
+/// e.g., "3 coins".
+fn invent_list(world: &World, items: &[(ID, String)]) -> Vec<String> {
+    // FIRST, group the items by name, retaining the order of first appearance.
+    let mut groups: Vec<(ID, &str, usize)> = Vec::new();
+
+    for (id, name) in items {
+        if let Some(group) = groups.iter_mut().find(|(_, gname, _)| gname == name) {
+            group.2 += 1;
+        } else {
+            groups.push((*id, name, 1));
         }
-        list.push_str(&thingc.name);
     }
 
-    list
+    // NEXT, format the groups.
+    groups
+        .into_iter()
+        .map(|(id, name, count)| {
+            if count == 1 {
+                name.to_string()
+            } else {
+                format!("{} {}", count, world.things[&id].plural())
+            }
+        })
+        .collect()
 }
 
 //-----------------------------------------------------------------------------
 // Helpers
 
-/// Get the specific type of prose from the entity
+/// Adds the Scenery prose of the owner's scenery to the buffer.  Scenery prose is
+/// part of the owner's description (e.g., a room's); a scenery thing's Thing prose
+/// is shown only when it is examined.
+fn put_scenery_prose(world: &World, owner: ID, buff: &mut ProseBuffer) {
+    for sid in phys::scenery(world, owner) {
+        if world.has_prose_type(sid, ProseType::Scenery) {
+            let prose = get_prose(world, sid, ProseType::Scenery);
+            // With a prose hook, result could be empty.
+            if !prose.is_empty() {
+                buff.puts(&prose);
+            }
+        }
+    }
+}
+
+/// Get the specific type of prose from the entity.  If the entity has no such prose,
+/// or no prose at all, returns a default description.
 pub fn get_prose(world: &World, id: ID, prose_type: ProseType) -> String {
-    assert!(world.has_prose(id), "Not prose: [{}]", id);
+    match world.proses.get(&id).and_then(|prosec| prosec.types.get(&prose_type)) {
+        Some(prose) => prose.as_string(world, id),
+        None => "You don't see anything special.".to_string(),
+    }
+}
 
-    let prosec = &world.proses[&id];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_builder::WorldBuilder;
+
+    #[test]
+    fn capture_restores_sink_after_panic() {
+        let events = capture(|| {
+            let result = std::panic::catch_unwind(|| {
+                capture(|| panic!("Oops"));
+            });
+            assert!(result.is_err());
+            info("Still here.");
+        });
+        assert_eq!(events, vec![OutputEvent::Info("Still here.".into())]);
+    }
 
-    if let Some(prose) = &prosec.types.get(&prose_type) {
-        prose.as_string(world, id)
-    } else {
-        "You don't see anything special.".to_string()
+    #[test]
+    fn prose_builder() {
+        let events = capture(|| prose("One.").when(false, "Two.").when(true, "Three.").para());
+        assert_eq!(events, vec![OutputEvent::Info("One.\nThree.".into())]);
+    }
+
+    #[test]
+    fn invent_list_groups() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("coin-1", "coin", "coin").location("here");
+        wb.thing("note", "note", "note").location("here");
+        wb.thing("coin-2", "coin", "coin").location("here");
+        wb.thing("mouse-1", "mouse", "mouse").plural("mice").location("here");
+        wb.thing("mouse-2", "mouse", "mouse").plural("mice").location("here");
+        let world = wb.world();
+        let here = world.lookup("here");
+
+        let list = invent_list(&world, &phys::named_contents(&world, here));
+        assert_eq!(list, vec!["2 coins", "note", "2 mice"]);
+    }
+
+    #[test]
+    fn exits_omit_hidden_dead_ends() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here")
+            .link(Dir::South, "there")
+            .link(Dir::North, "there")
+            .dead_end(Dir::East, "No way.")
+            .apparent_dead_end(Dir::West, "It only looked like a path.");
+        wb.room("there", "There");
+        let world = wb.world();
+
+        let events = capture(|| exits(&world, world.lookup("here")));
+        assert_eq!(events, vec![OutputEvent::Info("Obvious exits: north, south, west.".into())]);
+    }
+
+    #[test]
+    fn room_initial_prose() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here").prose("A room.");
+        wb.thing("note", "note", "note")
+            .location("here")
+            .on_initial("A crumpled note lies on the ground.");
+        wb.thing("coin", "coin", "coin").location("here");
+        let mut world = wb.world();
+        let here = world.lookup("here");
+
+        let events = capture(|| room(&world, here));
+        assert_eq!(
+            events,
+            vec![
+                OutputEvent::Room(
+                    "Here".into(),
+                    Some("A room.\nA crumpled note lies on the ground.".into())
+                ),
+                OutputEvent::ObjectList(vec!["coin".into()]),
+            ]
+        );
+
+        world.set_flag(world.lookup("note"), Flag::Handled);
+        let events = capture(|| room(&world, here));
+        assert_eq!(events[1], OutputEvent::ObjectList(vec!["note".into(), "coin".into()]));
+    }
+
+    #[test]
+    fn get_prose_without_prose() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("rock", "rock", "rock").location("here");
+        let world = wb.world();
+        let rock = world.lookup("rock");
+
+        assert!(!world.has_prose(rock));
+        assert_eq!(
+            get_prose(&world, rock, ProseType::Thing),
+            "You don't see anything special."
+        );
+    }
+
+    #[test]
+    fn room_ambience() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here")
+            .prose("A room.")
+            .on_enter_prose(&|w, _, buff| {
+                if w.clock() > 0 {
+                    buff.puts("A cold wind blows through.");
+                }
+            });
+        let mut world = wb.world();
+        let here = world.lookup("here");
+
+        let events = capture(|| room(&world, here));
+        assert_eq!(events, vec![OutputEvent::Room("Here".into(), Some("A room.".into()))]);
+
+        world.clock = 1;
+        let events = capture(|| room(&world, here));
+        assert_eq!(
+            events,
+            vec![OutputEvent::Room(
+                "Here".into(),
+                Some("A room.\nA cold wind blows through.".into())
+            )]
+        );
+    }
+
+    #[test]
+    fn player_inventory_bound() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("coin", "coin", "coin").location("PLAYER");
+        wb.thing("sword", "cursed sword", "sword").location("PLAYER").flag(Flag::Bound);
+        let world = wb.world();
+
+        let events = capture(|| player_inventory(&world, world.pid));
+        assert_eq!(
+            events,
+            vec![OutputEvent::Inventory(vec!["coin".into()], vec!["cursed sword".into()])]
+        );
+    }
+
+    #[test]
+    fn capture_events() {
+        let events = capture(|| {
+            act("Taken.");
+            error("You can't do that.");
+        });
+
+        assert_eq!(
+            events,
+            vec![
+                OutputEvent::Action("Taken.".into()),
+                OutputEvent::Error("You can't do that.".into())
+            ]
+        );
     }
 }
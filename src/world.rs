@@ -1,5 +1,7 @@
 //! The game world
 use crate::player_control::CommandHandler;
+use crate::rng::Rng;
+use crate::rule;
 use crate::entity::flag_set_component::*;
 use crate::entity::inventory_component::*;
 use crate::entity::location_component::*;
@@ -14,9 +16,16 @@ use crate::types::*;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 
 pub const LIMBO: ID = 0;
 
+/// The phases of the day, in order.  See `WorldQuery::phase`.
+pub const PHASES: [&str; 4] = ["morning", "afternoon", "evening", "night"];
+
+/// The default number of turns in each phase of the day.
+pub const DEFAULT_TURNS_PER_PHASE: Time = 25;
+
 /// The game state.  Uses a variant of the Entity-Component-System architecture.
 /// This struct provides many methods for querying and mutating entities.  These methods
 /// constitute a low-level interface for interacting with the world; e.g., `set_location()`
@@ -38,6 +47,37 @@ pub struct World {
     // The game clock
     pub clock: Time,
 
+    // The number of turns in each phase of the day
+    pub turns_per_phase: Time,
+
+    // The player's score
+    pub score: u32,
+
+    // If true, the player is notified when the score changes
+    pub notify: bool,
+
+    // The game's title, as set by the scenario
+    pub title: Option<String>,
+
+    // The game's introductory banner, as set by the scenario
+    pub intro: Option<String>,
+
+    // The order in which things are listed
+    pub list_order: ListOrder,
+
+    // If true, "go to" walks the player one step per turn; otherwise, he arrives
+    // in a single turn.
+    pub walk_by_steps: bool,
+
+    // The steps remaining on the player's "go to" route, if he's walking by steps
+    pub route: VecDeque<Dir>,
+
+    // The scenario's hints, with the number of texts shown for each
+    pub hints: Vec<Hint>,
+
+    // The random number generator.  All randomness should be drawn from this.
+    pub rng: Rng,
+
     //--------------------------------------------------------------------------------------------
     // Entity Components
     /// Tag Components: Identifiers for the entities.  This is a BTreeMap so that we can
@@ -81,6 +121,12 @@ pub struct World {
 
     // Mapping from verb synonyms to verbs
     pub synonyms: HashMap<String, String>,
+
+    // Mapping from player-defined aliases to their expansions
+    pub aliases: HashMap<String, String>,
+
+    // Mapping from easter-egg verbs, e.g., "xyzzy", to their responses
+    pub easter_eggs: HashMap<String, String>,
 }
 
 impl World {
@@ -94,6 +140,16 @@ impl World {
             tag_map: HashMap::new(),
             pid: 0,
             clock: 0,
+            turns_per_phase: DEFAULT_TURNS_PER_PHASE,
+            score: 0,
+            notify: true,
+            title: None,
+            intro: None,
+            list_order: ListOrder::CreationOrder,
+            walk_by_steps: false,
+            route: VecDeque::new(),
+            hints: Vec::new(),
+            rng: Rng::default(),
             tags: BTreeMap::new(),
             flag_sets: HashMap::new(),
             inventories: HashMap::new(),
@@ -106,26 +162,27 @@ impl World {
             command_handlers: Vec::new(),
             verbs: HashSet::new(),
             synonyms: HashMap::new(),
+            aliases: HashMap::new(),
+            easter_eggs: HashMap::new(),
         };
 
         // NEXT, add the standard verbs and synonyms
         // TODO: Decide where this should go.  Possibly not here.
         world.add_verb("go");
-
-        world.add_verb("north");
-        world.add_syn("north", "n");
-
-        world.add_verb("south");
-        world.add_syn("south", "s");
-
-        world.add_verb("east");
-        world.add_syn("east", "e");
-
-        world.add_verb("west");
-        world.add_syn("west", "w");
+        world.add_verb("enter");
+        world.add_verb("climb");
+        world.add_verb("back");
+        world.add_syn("back", "b");
+
+        // Abbreviations are handled by Dir::from_str.
+        for dir in &Dir::ALL {
+            world.add_verb(dir.name());
+        }
 
         world.add_verb("help");
+        world.add_verb("hint");
         world.add_verb("look");
+        world.add_verb("exits");
 
         world.add_verb("inventory");
         world.add_syn("inventory", "invent");
@@ -140,6 +197,24 @@ impl World {
         world.add_verb("drop");
 
         world.add_verb("read");
+        world.add_verb("open");
+        world.add_verb("close");
+        world.add_syn("close", "shut");
+        world.add_verb("smell");
+        world.add_verb("listen");
+        world.add_verb("touch");
+        world.add_syn("touch", "feel");
+        world.add_verb("give");
+        world.add_verb("use");
+
+        for how in &Manipulation::ALL {
+            world.add_verb(how.verb());
+        }
+
+        world.add_verb("notify");
+        world.add_verb("score");
+        world.add_verb("alias");
+        world.add_verb("unalias");
 
         world.add_verb("restart");
         world.add_verb("undo");
@@ -150,11 +225,11 @@ impl World {
         // NEXT, add debugging-only verbs
         world.add_verb("list");
         world.add_verb("dump");
-
-        // NEXT, add custom verbs
-        // TODO: Should be part of scenario, once the scenario can define
-        // custom command handlers.
-        world.add_verb("wash");
+        world.add_verb("rooms");
+        world.add_verb("verbs");
+        world.add_verb("stats");
+        world.add_verb("set");
+        world.add_verb("unset");
 
         world
     }
@@ -280,6 +355,9 @@ impl World {
 
     //--------------------------------------------------------------------------------------------
     // Flags
+    //
+    // These methods take entity IDs, as the engine's systems do.  Scenario hooks query
+    // flags by tag using WorldQuery::has(); use lookup() to get a tag's ID.
 
     /// Is the flag set on the entity?
     pub fn has_flag(&self, id: ID, flag: Flag) -> bool {
@@ -308,23 +386,159 @@ impl World {
         // Consider adding as_flags() to Entity
         fc.unset(flag);
     }
+
+    //--------------------------------------------------------------------------------------------
+    // State Comparison
+    //
+    // These are debugging aids, and are excluded from release builds.
+
+    /// Does this world have the same game state as the other?  Compares the data that
+    /// changes during play: the clock, score, flags, locations, inventories, rule
+    /// firings, and so forth.  Hooks, prose, and other static data are ignored.
+    #[cfg(any(test, debug_assertions))]
+    pub fn state_eq(&self, other: &World) -> bool {
+        self.diff(other).is_empty()
+    }
+
+    /// Lists the differences in game state between this world and the other, e.g.,
+    /// between the world before a turn and after it.  See `state_eq` for the data
+    /// compared.
+    #[cfg(any(test, debug_assertions))]
+    pub fn diff(&self, other: &World) -> Vec<String> {
+        let mut result = Vec::new();
+
+        // FIRST, the global data.
+        if self.clock != other.clock {
+            result.push(format!("clock: {} -> {}", self.clock, other.clock));
+        }
+
+        if self.score != other.score {
+            result.push(format!("score: {} -> {}", self.score, other.score));
+        }
+
+        if self.rng != other.rng {
+            result.push("rng: state changed".into());
+        }
+
+        if self.aliases != other.aliases {
+            result.push(format!("aliases: {:?} -> {:?}", self.aliases, other.aliases));
+        }
+
+        let shown = |w: &World| -> Vec<usize> { w.hints.iter().map(|h| h.shown).collect() };
+        if shown(self) != shown(other) {
+            result.push(format!("hints shown: {:?} -> {:?}", shown(self), shown(other)));
+        }
+
+        // NEXT, the entities.
+        let mut ids: Vec<ID> = self.tags.keys().chain(other.tags.keys()).cloned().collect();
+        ids.sort();
+        ids.dedup();
+
+        for id in ids {
+            let name = |w: &World| w.tags.get(&id).map(|tagc| format!("[{}] {}", id, tagc.tag));
+
+            match (name(self), name(other)) {
+                (Some(old), Some(new)) if old == new => {
+                    self.diff_entity(other, id, &new, &mut result);
+                }
+                (Some(old), None) => result.push(format!("{}: removed", old)),
+                (None, Some(new)) => result.push(format!("{}: added", new)),
+                (old, new) => result.push(format!("[{}]: {:?} -> {:?}", id, old, new)),
+            }
+        }
+
+        result
+    }
+
+    /// Adds the differences in the entity's state between this world and the other
+    /// to the result.
+    #[cfg(any(test, debug_assertions))]
+    fn diff_entity(&self, other: &World, id: ID, name: &str, result: &mut Vec<String>) {
+        // FIRST, flags.
+        let flags = |w: &World| w.flag_sets.get(&id).map_or_else(HashSet::new, |fc| fc.set.clone());
+        let (old_flags, new_flags) = (flags(self), flags(other));
+
+        let mut set: Vec<String> =
+            new_flags.difference(&old_flags).map(|f| format!("{:?}", f)).collect();
+        let mut unset: Vec<String> =
+            old_flags.difference(&new_flags).map(|f| format!("{:?}", f)).collect();
+        set.sort();
+        unset.sort();
+
+        for flag in set {
+            result.push(format!("{}: set {}", name, flag));
+        }
+
+        for flag in unset {
+            result.push(format!("{}: unset {}", name, flag));
+        }
+
+        // NEXT, location and inventory.
+        let loc = |w: &World| w.locations.get(&id).map(|locc| locc.id);
+        if let (Some(old), Some(new)) = (loc(self), loc(other)) {
+            if old != new {
+                result.push(format!("{}: moved {} -> {}", name, self.tag(old), other.tag(new)));
+            }
+        }
+
+        let inv = |w: &World| w.inventories.get(&id).map(|invc| invc.things.clone());
+        if inv(self) != inv(other) {
+            result.push(format!("{}: inventory {:?} -> {:?}", name, inv(self), inv(other)));
+        }
+
+        // NEXT, player and rule state.
+        if self.players.get(&id) != other.players.get(&id) {
+            result.push(format!(
+                "{}: player {:?} -> {:?}",
+                name,
+                self.players.get(&id),
+                other.players.get(&id)
+            ));
+        }
+
+        let firings = |w: &World| w.rules.get(&id).map(|r| (r.fire_count, r.last_fired));
+        if firings(self) != firings(other) {
+            result.push(format!("{}: firings {:?} -> {:?}", name, firings(self), firings(other)));
+        }
+    }
 }
 
-/// WorldQuery: A query interface, for use by scenario hooks
+/// WorldQuery: A query interface, for use by scenario hooks.  Where World's own
+/// methods take entity IDs, WorldQuery's take entity tags, as scenarios do.
 pub trait WorldQuery {
-    // Gets the value of the turn clock
+    /// Gets the value of the turn clock
     fn clock(&self) -> usize;
 
-    // Returns true if the given flag is set on the tagged entity, and false
-    // otherwise.
+    /// Gets the current phase of the day, "morning", "afternoon", "evening", or "night",
+    /// as derived from the clock.  The game begins in the morning.
+    fn phase(&self) -> &'static str;
+
+    /// Returns true if the given flag is set on the tagged entity, and false
+    /// otherwise.  Panics if the entity has no flag set.
     fn has(&self, tag: &str, flag: Flag) -> bool;
 
-    // Returns the tag of the tagged entity's location.
+    /// Returns the tag of the tagged entity's location, e.g.,
+    /// `w.has(&w.loc(PLAYER), HAS_WATER)`.  Panics if the entity has no location.
     fn loc(&self, tag: &str) -> String;
 
-    // Returns true if the tagged owner owns the tagged thing, and
-    // false otherwise
+    /// Returns true if the tagged owner owns the tagged thing, and
+    /// false otherwise
     fn owns(&self, owner: &str, thing: &str) -> bool;
+
+    /// Returns true if the player has handled the tagged thing, i.e., gotten
+    /// or examined it, and false otherwise.
+    fn handled(&self, tag: &str) -> bool;
+
+    /// Returns true if the tagged entity is in the player's current room, and
+    /// false otherwise.  Things the player is carrying are not "here".
+    fn here(&self, tag: &str) -> bool;
+
+    /// Returns the tag of the player's current room.
+    fn player_loc(&self) -> String;
+
+    /// Returns the tag of the entity that owns the rule being evaluated, e.g.,
+    /// `w.has(&w.owner(), ANGRY)`.  Panics if the rule has no owner.
+    fn owner(&self) -> String;
 }
 
 impl WorldQuery for World {
@@ -333,18 +547,20 @@ impl WorldQuery for World {
         self.clock
     }
 
+    // Gets the current phase of the day
+    fn phase(&self) -> &'static str {
+        PHASES[(self.clock / self.turns_per_phase) % PHASES.len()]
+    }
+
     /// Is the flag set on the entity?
     fn has(&self, tag: &str, flag: Flag) -> bool {
-        let id = self.lookup(tag);
-        assert!(self.has_flags(id), "Not a flag set: [{}]", id);
-        let fc = &self.flag_sets[&id];
-
-        fc.has(flag)
+        self.has_flag(self.lookup(tag), flag)
     }
 
     // Returns the tag of the tagged entity's location.
     fn loc(&self, tag: &str) -> String {
         let id = self.lookup(tag);
+        assert!(self.has_location(id), "Not located: [{}] {}", id, tag);
         self.tag(self.locations[&id].id)
     }
 
@@ -360,4 +576,133 @@ impl WorldQuery for World {
         }
     }
 
+    // Returns true if the player has handled the tagged thing.
+    fn handled(&self, tag: &str) -> bool {
+        self.has(tag, Flag::Handled)
+    }
+
+    // Returns true if the tagged entity is in the player's current room.
+    fn here(&self, tag: &str) -> bool {
+        let id = self.lookup(tag);
+        self.locations.get(&id).is_some_and(|locc| locc.id == self.locations[&self.pid].id)
+    }
+
+    // Returns the tag of the player's current room.
+    fn player_loc(&self) -> String {
+        self.tag(self.locations[&self.pid].id)
+    }
+
+    // Returns the tag of the entity that owns the rule being evaluated.
+    fn owner(&self) -> String {
+        self.tag(rule::owner().expect("The rule has no owner"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_builder::WorldBuilder;
+
+    #[test]
+    fn query_loc() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        let world = wb.world();
+
+        assert_eq!(world.loc("PLAYER"), "here");
+    }
+
+    #[test]
+    fn set_and_unset_flag() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("lamp", "lamp", "lamp").location("here");
+        let mut world = wb.world();
+        let lamp = world.lookup("lamp");
+
+        world.set_flag(lamp, Flag::LightSource);
+        assert!(world.has_flag(lamp, Flag::LightSource));
+        assert!(world.has("lamp", Flag::LightSource));
+
+        world.unset_flag(lamp, Flag::LightSource);
+        assert!(!world.has_flag(lamp, Flag::LightSource));
+    }
+
+    #[test]
+    fn has_by_tag_matches_has_flag() {
+        let world = crate::scenario::build();
+
+        for tag in &["hands", "note", "sword", "grotto", "PLAYER"] {
+            let id = world.lookup(tag);
+            for flag in &[Flag::User("DIRTY"), Flag::User("HAS_WATER"), Flag::Scenery] {
+                assert_eq!(world.has(tag, *flag), world.has_flag(id, *flag), "{} {:?}", tag, flag);
+            }
+        }
+    }
+
+    #[test]
+    fn world_diff() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("coin", "coin", "coin").location("here");
+        let before = wb.world();
+        let mut after = before.clone();
+        assert!(before.state_eq(&after));
+
+        let coin = after.lookup("coin");
+        after.clock = 1;
+        after.set_flag(coin, Flag::Handled);
+        assert!(!before.state_eq(&after));
+        assert_eq!(
+            before.diff(&after),
+            vec!["clock: 0 -> 1".to_string(), format!("[{}] coin: set Handled", coin)]
+        );
+    }
+
+    #[test]
+    fn query_phase() {
+        let mut world = World::new();
+        world.turns_per_phase = 10;
+        assert_eq!(world.phase(), "morning");
+
+        world.clock = 19;
+        assert_eq!(world.phase(), "afternoon");
+
+        world.clock = 35;
+        assert_eq!(world.phase(), "night");
+
+        world.clock = 40;
+        assert_eq!(world.phase(), "morning");
+    }
+
+    #[test]
+    fn query_here() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.room("there", "There");
+        wb.thing("coin", "coin", "coin").location("here");
+        wb.thing("rock", "rock", "rock").location("there");
+        wb.thing("key", "key", "key").location("PLAYER");
+        let world = wb.world();
+
+        assert_eq!(world.player_loc(), "here");
+        assert!(world.here("coin"));
+        assert!(!world.here("rock"));
+        assert!(!world.here("key"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not located: [2] here")]
+    fn query_loc_not_located() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        let world = wb.world();
+
+        world.loc("here");
+    }
 }
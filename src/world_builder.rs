@@ -15,6 +15,8 @@ use crate::entity::rule_component::*;
 use crate::entity::thing_component::*;
 use crate::phys;
 use crate::player_control::CommandHandler;
+use crate::script::Script;
+use crate::visual;
 use crate::types::*;
 use crate::world::World;
 
@@ -27,16 +29,55 @@ pub const LIMBO: &str = "LIMBO";
 /// The tag of the PLAYER entity.
 pub const PLAYER: &str = "PLAYER";
 
-/// Events for which rules can be written.
+/// A stand-in for the tag of the entity that owns a rule, for use in the rule's
+/// actions, e.g., `wb.thing("troll", ...).rule(&Turn).set_flag(THIS, ANGRY)`.
+/// Predicates use `WorldQuery::owner()` instead.
+pub const THIS: &str = "THIS";
+
+/// Events for which rules can be written.  Entities are referred to by tag; allow() and
+/// on() convert them to ID-based Events.  If a tag doesn't name the expected kind of
+/// entity (e.g., it's misspelled), world() will panic with an "Expected ..." message.
 pub enum WBEvent<'a> {
+    /// A game turn has elapsed
+    Turn,
+
     /// The player gets (or tries to get) the tagged entity
     GetThing(&'a str),
 
+    /// The player drops (or tries to drop) the tagged entity
+    DropThing(&'a str),
+
     /// The player reads (or tries to read) the tagged entity
     ReadThing(&'a str),
 
     /// The player enters (or tries to enter) the tagged entity
     EnterRoom(&'a str),
+
+    /// The player leaves (or tries to leave) the tagged entity
+    LeaveRoom(&'a str),
+
+    /// GiveThing(thing, recipient): The player gives (or tries to give) the tagged
+    /// thing to the tagged recipient.
+    GiveThing(&'a str, &'a str),
+
+    /// TakeFrom(thing, container): The player takes (or tries to take) the tagged
+    /// thing out of the tagged container.
+    TakeFrom(&'a str, &'a str),
+
+    /// The tagged NPC sees the player enter its room
+    NpcSees(&'a str),
+
+    /// UseOn(tool, target): The player uses (or tries to use) the tagged tool on the
+    /// tagged target.
+    UseOn(&'a str, &'a str),
+
+    /// Manipulate(thing, manipulation): The player pushes, pulls, etc., (or tries to)
+    /// the tagged thing.
+    Manipulate(&'a str, Manipulation),
+
+    /// Custom(name, entity): A scenario-specific event concerning the tagged entity,
+    /// fired by a hook or rule.
+    Custom(&'static str, &'a str),
 }
 
 /// Expectations, to be checked when world-building is complete.
@@ -48,6 +89,13 @@ enum Is {
     /// The entity is the player
     Player(ID),
 
+    /// The entity is explicitly defined, i.e., by room(), thing(), etc., or is
+    /// LIMBO or the player.
+    Defined(ID),
+
+    /// The entity is an NPC.
+    Npc(ID),
+
     /// The entity is a room.
     Room(ID),
 
@@ -77,6 +125,17 @@ enum Is {
 pub struct WorldBuilder {
     world: World,
     expectations: HashSet<Is>,
+
+    // Return links added by link_both(): (room, dir, dest).  These are added when
+    // the world is complete, since the destination might not exist yet.
+    return_links: Vec<(ID, Dir, ID)>,
+
+    // The entities explicitly defined by room(), thing(), rule(), etc., as opposed
+    // to those merely referenced, e.g., as link destinations.
+    defined: HashSet<ID>,
+
+    // Entities that were defined more than once, probably by mistake.
+    redefined: Vec<ID>,
 }
 
 impl WorldBuilder {
@@ -89,6 +148,9 @@ impl WorldBuilder {
         let mut this = Self {
             world: World::new(),
             expectations: HashSet::new(),
+            return_links: Vec::new(),
+            defined: HashSet::new(),
+            redefined: Vec::new(),
         };
 
         // NEXT, create LIMBO, the container for things which aren't anywhere else.
@@ -110,25 +172,97 @@ impl WorldBuilder {
         this
     }
 
+    /// Sets the game's title, for display when the game begins.
+    pub fn title(&mut self, title: &str) {
+        self.world.title = Some(title.into());
+    }
+
+    /// Sets the game's introductory banner, displayed after the title when the
+    /// game begins.  The banner can use conmark syntax.
+    pub fn intro(&mut self, text: &str) {
+        self.world.intro = Some(text.trim().into());
+    }
+
+    /// Sets the order in which things are listed in room descriptions and the
+    /// player's inventory.  Defaults to creation order.
+    pub fn list_order(&mut self, order: ListOrder) {
+        self.world.list_order = order;
+    }
+
+    /// Sets the number of turns in each phase of the day; see `WorldQuery::phase`.
+    pub fn turns_per_phase(&mut self, turns: Time) {
+        assert!(turns > 0, "turns_per_phase must be positive");
+        self.world.turns_per_phase = turns;
+    }
+
+    /// Determines how "go to" moves the player: one step per turn, so that the world
+    /// keeps moving around him, or (the default) all the way in a single turn.
+    pub fn walk_by_steps(&mut self, flag: bool) {
+        self.world.walk_by_steps = flag;
+    }
+
+    /// Seeds the game's random number generator.  Scenarios that don't
+    /// call this get a fixed default seed.
+    pub fn seed(&mut self, seed: u64) {
+        self.world.rng.reseed(seed);
+    }
+
+    /// Adds a flavor verb, e.g., "xyzzy", that does nothing but print the response.
+    pub fn easter_egg(&mut self, word: &str, response: &str) {
+        assert!(
+            !self.world.verbs.contains(word),
+            "easter egg shadows existing verb: {}",
+            word
+        );
+        self.world.add_verb(word);
+        self.world.easter_eggs.insert(word.into(), response.trim().into());
+    }
+
+    /// Defines an input alias: when the first word of a command is the alias, it's
+    /// replaced by the expansion.  Unlike the player's "alias" command, this can
+    /// shadow a real verb.
+    pub fn alias(&mut self, name: &str, expansion: &str) {
+        self.world.aliases.insert(name.into(), expansion.into());
+    }
+
+    /// Adds a progressive hint, shown by the "hint" command while the predicate is true.
+    /// The texts should go from vague to specific; each request shows the next one.
+    pub fn hint_for(&mut self, predicate: RulePredicate, texts: &[&str]) {
+        assert!(!texts.is_empty(), "Hint has no texts");
+        self.world.hints.push(Hint {
+            predicate,
+            texts: texts.iter().map(|t| t.trim().to_string()).collect(),
+            shown: 0,
+        });
+    }
+
     /// Adds a custom command consisting of a single verb.
     pub fn verb(&mut self, word: &str, hook: CommandHook) {
-        // TODO: Add to list of verbs
+        self.add_verb(word);
         self.world.command_handlers.push(CommandHandler::verb(word, hook));
     }
 
     /// Adds a custom command triggered by a specific verb and noun.
     pub fn verb_noun(&mut self, verb: &str, noun: &str, hook: CommandHook) {
-        // TODO: Add to list of verbs
+        self.add_verb(verb);
         self.world.command_handlers.push(CommandHandler::verb_noun(verb, noun, hook));
     }
 
     /// Adds a custom command triggered by a specific verb and a noun representing
     /// a thing that's visible to the player.
     pub fn verb_visible(&mut self, verb: &str, hook: CommandHook) {
-        // TODO: Add to list of verbs
+        self.add_verb(verb);
         self.world.command_handlers.push(CommandHandler::verb_visible(verb, hook));
     }
 
+    /// Adds a custom command's verb to the world's verbs, if it isn't already known.
+    /// Several custom commands may share a verb, and may extend a built-in one.
+    fn add_verb(&mut self, verb: &str) {
+        if !self.world.verbs.contains(verb) {
+            self.world.add_verb(verb);
+        }
+    }
+
     /// Configures the player.
     pub fn player(&mut self) -> PlayerBuilder {
         PlayerBuilder {
@@ -139,6 +273,7 @@ impl WorldBuilder {
     /// Creates or configures a room.
     pub fn room(&mut self, tag: &str, name: &str) -> RoomBuilder {
         let id = self.world.alloc(tag);
+        self.define(id);
 
         self.world.rooms.insert(id, RoomComponent::new(name));
         self.add_inventory(id);
@@ -163,6 +298,7 @@ impl WorldBuilder {
     /// Creates or configures a thing.
     pub fn thing(&mut self, tag: &str, name: &str, noun: &str) -> ThingBuilder {
         let id = self.world.alloc(tag);
+        self.define(id);
 
         self.world.things.insert(id, ThingComponent::new(name, noun));
         self.add_location(id);
@@ -178,6 +314,7 @@ impl WorldBuilder {
     /// Creates and configures a rule that will be triggered every turn.
     pub fn rule(&mut self, tag: &str) -> RuleBuilder {
         let id = self.world.alloc(tag);
+        self.define(id);
 
         self.world.rules.insert(id, RuleComponent::new());
         self.add_flag_set(id);
@@ -206,43 +343,124 @@ impl WorldBuilder {
     }
 
 
-    /// Completes world-building, after checking that all expectations are met.
-    pub fn world(self) -> World {
-        for expectation in self.expectations {
-            match expectation {
+    /// Returns warnings about likely mistakes that don't prevent the world from
+    /// being built, e.g., entities whose tags were defined more than once.
+    pub fn warnings(&self) -> Vec<String> {
+        self.redefined
+            .iter()
+            .map(|id| format!("Entity defined more than once: {}", self.idtag(*id)))
+            .collect()
+    }
+
+    /// Completes world-building, after checking that all expectations are met and
+    /// that the world is internally consistent.  Panics with a list of all of the
+    /// problems found, if any; outputs any warnings.
+    pub fn world(mut self) -> World {
+        let mut problems: Vec<String> = Vec::new();
+
+        for warning in self.warnings() {
+            visual::error(&format!("Warning: {}", warning));
+        }
+
+        // FIRST, add the return links.  An explicit link in the same direction takes
+        // precedence.  If the destination isn't a room, the link check below will
+        // catch it.
+        for (room, dir, dest) in std::mem::take(&mut self.return_links) {
+            if let Some(roomc) = self.world.rooms.get_mut(&room) {
+                roomc.links.entry(dir).or_insert(LinkDest::Room(dest));
+            }
+        }
+
+        // NEXT, check the expectations.
+        for expectation in &self.expectations {
+            match *expectation {
                 Is::Book(id) => {
-                    assert!(self.world.has_prose_type(id, ProseType::Book),
-                        "Expected book prose: [{}] {}",
-                        id, self.world.tag(id));
+                    if !self.world.has_prose_type(id, ProseType::Book) {
+                        problems.push(format!("Expected book prose: {}", self.idtag(id)));
+                    }
                 }
                 Is::Player(id) => {
-                    assert!(self.world.is_player(id),
-                        "Expected player: [{}] {}",
-                        id, self.world.tag(id));
+                    if !self.world.is_player(id) {
+                        problems.push(format!("Expected player: {}", self.idtag(id)));
+                    }
                 }
                 Is::Room(id) => {
-                    assert!(self.world.is_room(id),
-                        "Expected room: [{}] {}",
-                        id, self.world.tag(id));
+                    if !self.world.is_room(id) {
+                        problems.push(format!("Expected room: {}", self.idtag(id)));
+                    }
                 }
                 Is::Thing(id) => {
-                    assert!(self.world.is_thing(id),
-                        "Expected thing: [{}] {}",
-                        id, self.world.tag(id));
+                    if !self.world.is_thing(id) {
+                        problems.push(format!("Expected thing: {}", self.idtag(id)));
+                    }
                 }
+                Is::Defined(id) => {
+                    if !self.defined.contains(&id) && id != self.world.pid && id != crate::world::LIMBO {
+                        problems.push(format!("Undefined entity: {}", self.idtag(id)));
+                    }
+                }
+                Is::Npc(id) => {
+                    if !self.world.has_flags(id) || !self.world.has_flag(id, Flag::Npc) {
+                        problems.push(format!("Expected NPC: {}", self.idtag(id)));
+                    }
+                }
+            }
+        }
+
+        // NEXT, every link must lead to a room.
+        for (id, roomc) in &self.world.rooms {
+            for (dir, dest) in &roomc.links {
+                if let LinkDest::Room(dest) = dest {
+                    if !self.world.is_room(*dest) {
+                        problems.push(format!("Link {:?} from {} leads to non-room: {}",
+                            dir, self.idtag(*id), self.idtag(*dest)));
+                    }
+                }
+            }
+        }
+
+        // NEXT, everything with a location must be in something that has an inventory.
+        for (id, locc) in &self.world.locations {
+            if !self.world.has_inventory(locc.id) {
+                problems.push(format!("Location of {} has no inventory: {}",
+                    self.idtag(*id), self.idtag(locc.id)));
             }
         }
+
+        // NEXT, the player must start somewhere.
+        if self.world.locations[&self.world.pid].id == crate::world::LIMBO {
+            problems.push("Player has no initial location".into());
+        }
+
+        if !problems.is_empty() {
+            problems.sort();
+            panic!("Invalid scenario:\n{}", problems.join("\n"));
+        }
+
         self.world
     }
 
     //-------------------------------------------------------------------------------------------
     // Utility methods
 
+    /// Formats an entity's ID and tag for use in error messages.
+    fn idtag(&self, id: ID) -> String {
+        format!("[{}] {}", id, self.world.tag(id))
+    }
+
     /// Adds an expectation for later checking.
     fn expect(&mut self, expectation: Is) {
         self.expectations.insert(expectation);
     }
 
+    /// Records that the entity has been explicitly defined, noting if it was
+    /// already defined.
+    fn define(&mut self, id: ID) {
+        if !self.defined.insert(id) && !self.redefined.contains(&id) {
+            self.redefined.push(id);
+        }
+    }
+
     /// Adds a location to an entity if it doesn't have one.  The entity will initially
     /// be in LIMBO.
     fn add_location(&mut self, id: ID) {
@@ -311,33 +529,114 @@ impl WorldBuilder {
         self.world.proses.get_mut(&id).unwrap().types.insert(prose_type, prose);
     }
 
-    /// Creates and configures a rule that will be triggered when a specific
-    /// event occurs.
-    fn build_event_rule(&mut self, kind: &str, evt: &WBEvent, mut rulec: RuleComponent) -> RuleBuilder {
-        // FIRST, compute the internal event.
-        let tag: String = match evt {
+    /// Converts a world-building event into an internal event, adding expectations
+    /// for the entities it references.  Also returns a name for the event, for use
+    /// in rule tags, e.g., "get-sword".
+    fn event(&mut self, evt: &WBEvent) -> (Event, String) {
+        let pid = self.world.pid;
+
+        match evt {
+            WBEvent::Turn => (Event::Turn, format!("turn-{}", self.world.rules.len() + 1)),
             WBEvent::GetThing(thing_tag) => {
                 let tid = self.world.alloc(thing_tag);
-                rulec.event = Event::GetThing(self.world.pid, tid);
                 self.expect(Is::Thing(tid));
-                format!("{}-get-{}", kind, thing_tag)
+                (Event::GetThing(pid, tid), format!("get-{}", thing_tag))
+            }
+            WBEvent::DropThing(thing_tag) => {
+                let tid = self.world.alloc(thing_tag);
+                self.expect(Is::Thing(tid));
+                (Event::DropThing(pid, tid), format!("drop-{}", thing_tag))
             }
             WBEvent::ReadThing(thing_tag) => {
                 let tid = self.world.alloc(thing_tag);
-                rulec.event = Event::ReadThing(self.world.pid, tid);
                 self.expect(Is::Thing(tid));
                 self.expect(Is::Book(tid));
-                format!("{}-read-{}", kind, thing_tag)
+                (Event::ReadThing(pid, tid), format!("read-{}", thing_tag))
             }
             WBEvent::EnterRoom(room_tag) => {
                 let rid = self.world.alloc(room_tag);
-                rulec.event = Event::EnterRoom(self.world.pid, rid);
                 self.expect(Is::Room(rid));
-                format!("{}-enter-{}", kind, room_tag)
+                (Event::EnterRoom(pid, rid), format!("enter-{}", room_tag))
             }
-        };
+            WBEvent::LeaveRoom(room_tag) => {
+                let rid = self.world.alloc(room_tag);
+                self.expect(Is::Room(rid));
+                (Event::LeaveRoom(pid, rid), format!("leave-{}", room_tag))
+            }
+            WBEvent::GiveThing(thing_tag, recipient_tag) => {
+                let tid = self.world.alloc(thing_tag);
+                let rid = self.world.alloc(recipient_tag);
+                self.expect(Is::Thing(tid));
+                self.expect(Is::Thing(rid));
+                (
+                    Event::GiveThing(pid, tid, rid),
+                    format!("give-{}-to-{}", thing_tag, recipient_tag),
+                )
+            }
+            WBEvent::TakeFrom(thing_tag, container_tag) => {
+                let tid = self.world.alloc(thing_tag);
+                let cid = self.world.alloc(container_tag);
+                self.expect(Is::Thing(tid));
+                self.expect(Is::Thing(cid));
+                (
+                    Event::TakeFrom(pid, tid, cid),
+                    format!("take-{}-from-{}", thing_tag, container_tag),
+                )
+            }
+            WBEvent::NpcSees(npc_tag) => {
+                let nid = self.world.alloc(npc_tag);
+                self.expect(Is::Thing(nid));
+                self.expect(Is::Npc(nid));
+                (Event::NpcSees(nid, pid), format!("sees-{}", npc_tag))
+            }
+            WBEvent::UseOn(tool_tag, target_tag) => {
+                let tid = self.world.alloc(tool_tag);
+                let gid = self.world.alloc(target_tag);
+                self.expect(Is::Thing(tid));
+                self.expect(Is::Thing(gid));
+                (
+                    Event::UseOn(pid, tid, gid),
+                    format!("use-{}-on-{}", tool_tag, target_tag),
+                )
+            }
+            WBEvent::Manipulate(thing_tag, how) => {
+                let tid = self.world.alloc(thing_tag);
+                self.expect(Is::Thing(tid));
+                (
+                    Event::Manipulate(pid, tid, *how),
+                    format!("{}-{}", how.verb(), thing_tag),
+                )
+            }
+            WBEvent::Custom(name, tag) => {
+                let id = self.world.alloc(tag);
+                self.expect(Is::Defined(id));
+                (Event::Custom(name, id), format!("{}-{}", name, tag))
+            }
+        }
+    }
+
+    /// Allocates the tagged entity referred to by the rule's actions.  THIS refers to
+    /// the rule's owner.
+    fn rule_entity(&mut self, rule: ID, tag: &str) -> ID {
+        if tag == THIS {
+            self.world.rules[&rule]
+                .owner
+                .unwrap_or_else(|| panic!("{} used in a rule with no owner", THIS))
+        } else {
+            self.world.alloc(tag)
+        }
+    }
+
+    /// Creates and configures a rule that will be triggered when a specific
+    /// event occurs.
+    fn build_event_rule(&mut self, kind: &str, evt: &WBEvent, mut rulec: RuleComponent) -> RuleBuilder {
+        // FIRST, compute the internal event and the rule's tag.
+        let (event, name) = self.event(evt);
+        rulec.event = event;
+        let tag = format!("{}-{}", kind, name);
 
         let id = self.world.alloc(&tag);
+        self.define(id);
         self.world.rules.insert(id, rulec);
         self.add_flag_set(id);
 
@@ -408,6 +707,31 @@ impl<'a> RoomBuilder<'a> {
         self
     }
 
+    /// Adds a prose hook that appends dynamic text to the room's full description,
+    /// e.g., "A cold wind blows through." only at night.  The hook may add nothing.
+    pub fn on_enter_prose(self, hook: EntityProseHook) -> RoomBuilder<'a> {
+        self.wb.add_prose_hook(self.id, ProseType::Ambience, hook);
+        self
+    }
+
+    /// Adds prose describing how the room smells, for "smell".
+    pub fn on_smell(self, text: &str) -> RoomBuilder<'a> {
+        self.wb.add_prose(self.id, ProseType::Smell, text);
+        self
+    }
+
+    /// Adds prose describing how the room sounds, for "listen".
+    pub fn on_sound(self, text: &str) -> RoomBuilder<'a> {
+        self.wb.add_prose(self.id, ProseType::Sound, text);
+        self
+    }
+
+    /// Adds prose describing how the room feels, for "touch".
+    pub fn on_touch(self, text: &str) -> RoomBuilder<'a> {
+        self.wb.add_prose(self.id, ProseType::Touch, text);
+        self
+    }
+
     /// Sets a flag on the room.
     pub fn flag(self, flag: Flag) -> RoomBuilder<'a> {
         self.wb.add_flag(self.id, flag);
@@ -417,9 +741,9 @@ impl<'a> RoomBuilder<'a> {
     /// Creates a link from this room to another room given the direction and
     /// the other room's tag.
     pub fn link(self, dir: Dir, room_tag: &str) -> RoomBuilder<'a> {
-        // FIRST, get the id of the destination.
+        // FIRST, get the id of the destination.  world() will verify that
+        // it's a room.
         let dest = self.wb.world.alloc(room_tag);
-        self.wb.expect(Is::Room(dest));
 
         let link = LinkDest::Room(dest);
         self.wb.world.rooms.get_mut(&self.id).unwrap().links.insert(dir, link);
@@ -427,9 +751,34 @@ impl<'a> RoomBuilder<'a> {
         self
     }
 
-    /// Adds a dead end in the given direction.
+    /// Sets the message to display when the player tries to go in a direction that has
+    /// no link, in place of "You can't go that way."
+    pub fn no_exit(self, dir: Dir, text: &str) -> RoomBuilder<'a> {
+        self.wb.world.rooms.get_mut(&self.id).unwrap().no_exits.insert(dir, text.trim().into());
+        self
+    }
+
+    /// Creates a link from this room to another room given the direction and the
+    /// other room's tag, and a link in the opposite direction from the other room
+    /// back to this one.  Use link() for one-way links.
+    pub fn link_both(self, dir: Dir, room_tag: &str) -> RoomBuilder<'a> {
+        let dest = self.wb.world.alloc(room_tag);
+        self.wb.return_links.push((dest, dir.opposite(), self.id));
+        self.link(dir, room_tag)
+    }
+
+    /// Adds a dead end in the given direction.  The dead end is not listed as
+    /// an exit.
     pub fn dead_end(self, dir: Dir, text: &str) -> RoomBuilder<'a> {
-        let dead_end = LinkDest::DeadEnd(text.into());
+        let dead_end = LinkDest::DeadEnd(text.into(), false);
+        self.wb.world.rooms.get_mut(&self.id).unwrap().links.insert(dir, dead_end);
+        self
+    }
+
+    /// Adds a dead end in the given direction that is listed as an exit, i.e.,
+    /// it looks like a real exit until the player tries it.
+    pub fn apparent_dead_end(self, dir: Dir, text: &str) -> RoomBuilder<'a> {
+        let dead_end = LinkDest::DeadEnd(text.into(), true);
         self.wb.world.rooms.get_mut(&self.id).unwrap().links.insert(dir, dead_end);
         self
     }
@@ -448,6 +797,65 @@ impl<'a> ThingBuilder<'a> {
         self.wb.set_location(self.id, loc);
         self
     }
+
+    /// Puts the thing inside another thing, e.g., a coin in a chest, giving the
+    /// container an inventory if it lacks one.  The container must be a thing.
+    pub fn inside(self, container_tag: &str) -> ThingBuilder<'a> {
+        self.wb.set_location(self.id, container_tag);
+        let container = self.wb.world.lookup(container_tag);
+        self.wb.expect(Is::Thing(container));
+        self
+    }
+
+    /// Creates and configures a rule, belonging to this thing, that will be triggered
+    /// when a specific event occurs, e.g., `wb.thing(...).rule(&NpcSees("troll"))`.
+    /// The rule is active only while the thing is in the world, i.e., not in LIMBO.
+    /// The rule's predicate can refer to the thing as `w.owner()`, and its actions
+    /// as THIS.  This ends the thing's configuration.
+    pub fn rule(self, evt: &WBEvent) -> RuleBuilder<'a> {
+        let owner = self.id;
+        let rb = self.wb.on(evt);
+        rb.wb.world.rules.get_mut(&rb.id).unwrap().owner = Some(owner);
+        rb
+    }
+
+    /// Sets the thing's plural name, for use when several things with the same
+    /// name are listed together.  Defaults to the name plus "s".
+    pub fn plural(self, plural: &str) -> ThingBuilder<'a> {
+        self.wb.world.things.get_mut(&self.id).unwrap().plural = Some(plural.into());
+        self
+    }
+
+    /// Adds a multi-word noun for the thing, e.g., "brass lantern".
+    pub fn phrase(self, phrase: &str) -> ThingBuilder<'a> {
+        let thingc = self.wb.world.things.get_mut(&self.id).unwrap();
+        thingc.phrases.push(phrase.to_lowercase());
+        self
+    }
+
+    /// Sets the room the player goes to on entering the thing, e.g., via
+    /// "enter portal".
+    pub fn leads_to(self, room_tag: &str) -> ThingBuilder<'a> {
+        let room = self.wb.world.alloc(room_tag);
+        self.wb.expect(Is::Room(room));
+        self.wb.world.things.get_mut(&self.id).unwrap().leads_to = Some(room);
+        self
+    }
+
+    /// Gives the thing an inventory, so that it can hold other things, e.g.,
+    /// an NPC that can be given things.
+    pub fn inventory(self) -> ThingBuilder<'a> {
+        self.wb.add_inventory(self.id);
+        self
+    }
+
+    /// Makes the thing an NPC, i.e., a character that gets an NpcSees event when
+    /// the player enters its room.
+    pub fn npc(self) -> ThingBuilder<'a> {
+        self.wb.add_flag(self.id, Flag::Npc);
+        self
+    }
+
     /// Adds descriptive prose to the thing.
     pub fn on_examine(self, text: &str) -> ThingBuilder<'a> {
         self.wb.add_prose(self.id, ProseType::Thing, text);
@@ -487,6 +895,31 @@ impl<'a> ThingBuilder<'a> {
         self
     }
 
+    /// Adds prose describing how the thing smells, for "smell".
+    pub fn on_smell(self, text: &str) -> ThingBuilder<'a> {
+        self.wb.add_prose(self.id, ProseType::Smell, text);
+        self
+    }
+
+    /// Adds prose describing how the thing sounds, for "listen".
+    pub fn on_sound(self, text: &str) -> ThingBuilder<'a> {
+        self.wb.add_prose(self.id, ProseType::Sound, text);
+        self
+    }
+
+    /// Adds prose describing how the thing feels, for "touch".
+    pub fn on_touch(self, text: &str) -> ThingBuilder<'a> {
+        self.wb.add_prose(self.id, ProseType::Touch, text);
+        self
+    }
+
+    /// Adds the thing's initial mention in a room description, used in place of
+    /// the object list until the player has handled the thing.
+    pub fn on_initial(self, text: &str) -> ThingBuilder<'a> {
+        self.wb.add_prose(self.id, ProseType::Initial, text);
+        self
+    }
+
     /// Sets a flag on the thing.
     pub fn flag(self, flag: Flag) -> ThingBuilder<'a> {
         self.wb.add_flag(self.id, flag);
@@ -522,9 +955,33 @@ impl<'a> RuleBuilder<'a> {
 
     /// Specifies that the rule should execute no more than once.
     pub fn once_only(self) -> RuleBuilder<'a> {
-        let rulec = &self.wb.world.rules[&self.id];
-        assert!(!rulec.is_guard, "Cannot set 'once_only' predicate on allow() rule: {}", self.tag);
-        self.wb.add_flag(self.id, Flag::FireOnce);
+        self.times(1)
+    }
+
+    /// Specifies that the rule fires at most the given number of times, e.g., a
+    /// fountain that dispenses three coins.
+    pub fn times(self, n: u32) -> RuleBuilder<'a> {
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        assert!(!rulec.is_guard, "Cannot set 'times' limit on allow() rule: {}", self.tag);
+        rulec.limit = Some(n);
+        self
+    }
+
+    /// Specifies that once the rule fires, it can't fire again until the given
+    /// number of turns have passed, e.g., an NPC that shouts every five turns.
+    pub fn cooldown(self, turns: Time) -> RuleBuilder<'a> {
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        assert!(!rulec.is_guard, "Cannot set 'cooldown' on allow() rule: {}", self.tag);
+        rulec.cooldown = turns;
+        self
+    }
+
+    /// Specifies the rule's priority.  Rules with higher priority fire (or guard) before
+    /// rules with lower priority; rules with the same priority fire in the order
+    /// they were created.  Defaults to 0.
+    pub fn priority(self, priority: i32) -> RuleBuilder<'a> {
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        rulec.priority = priority;
         self
     }
 
@@ -536,11 +993,42 @@ impl<'a> RuleBuilder<'a> {
         self
     }
 
+    /// Fires the event when the rule fires, triggering any rules that respond to it;
+    /// e.g., taking the idol might also fire the event of entering the trap room.
+    pub fn fire_event(self, evt: &WBEvent) -> RuleBuilder<'a> {
+        let (event, _) = self.wb.event(evt);
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        rulec.script.fire_event(event);
+        self
+    }
+
+    /// Adds points to the player's score, notifying the player.
+    pub fn add_score(self, points: u32) -> RuleBuilder<'a> {
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        rulec.script.add_score(points);
+        self
+    }
+
+    /// Adds points to the player's score without notifying the player.
+    pub fn add_score_quietly(self, points: u32) -> RuleBuilder<'a> {
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        rulec.script.add_score_quietly(points);
+        self
+    }
+
+    /// Prints the tagged entity's description, e.g., to show a thing that the
+    /// rule has changed.
+    pub fn describe(self, tag: &str) -> RuleBuilder<'a> {
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        rulec.script.describe(tag);
+        self
+    }
+
     /// Sets a flag on the entity.
     pub fn set_flag(self, tag: &str, flag: Flag) -> RuleBuilder<'a> {
         // FIRST, get the entity on which we'll be adding the flag, and
         // make sure it's the kind of thing we can set a flag on.
-        let id = self.wb.world.alloc(tag);
+        let id = self.wb.rule_entity(self.id, tag);
         self.wb.add_flag_set(id);
 
         // NEXT, add the action.
@@ -553,7 +1041,7 @@ impl<'a> RuleBuilder<'a> {
     pub fn unset_flag(self, tag: &str, flag: Flag) -> RuleBuilder<'a> {
         // FIRST, get the entity on which we'll be adding the flag, and
         // make sure it's the kind of thing we can set a flag on.
-        let id = self.wb.world.alloc(tag);
+        let id = self.wb.rule_entity(self.id, tag);
         self.wb.add_flag_set(id);
 
         // NEXT, add the action.
@@ -562,10 +1050,31 @@ impl<'a> RuleBuilder<'a> {
         self
     }
 
+    /// Adds actions that execute only if the tagged entity has the flag when the
+    /// rule fires: those added by `then` if it has the flag, and those added by
+    /// `otherwise` if it doesn't.
+    pub fn when_flag_else(
+        self,
+        tag: &str,
+        flag: Flag,
+        then: &dyn Fn(&mut Script),
+        otherwise: &dyn Fn(&mut Script),
+    ) -> RuleBuilder<'a> {
+        // FIRST, get the entity whose flag we'll be checking, and
+        // make sure it's the kind of thing that has flags.
+        let id = self.wb.rule_entity(self.id, tag);
+        self.wb.add_flag_set(id);
+
+        // NEXT, add the action.
+        let rulec = &mut self.wb.world.rules.get_mut(&self.id).unwrap();
+        rulec.script.when_flag_else(tag, flag, then, otherwise);
+        self
+    }
+
     /// Moves a thing to LIMBO
     pub fn forget(self, thing: &str) -> RuleBuilder<'a> {
         // FIRST, get the entity which we'll be forgetting.
-        let id = self.wb.world.alloc(thing);
+        let id = self.wb.rule_entity(self.id, thing);
         self.wb.expect(Is::Thing(id));
 
         // NEXT, add the action.
@@ -598,3 +1107,161 @@ impl<'a> RuleBuilder<'a> {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "leads to non-room: [3] nowhere")]
+    fn world_dangling_link() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here").link(Dir::North, "nowhere");
+        wb.world();
+    }
+
+    #[test]
+    fn feature() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.feature("pool", "pool", "pool")
+            .location("here")
+            .on_scenery("There's a pool here.")
+            .on_examine("It's wet.");
+        let world = wb.world();
+        let pool = world.lookup("pool");
+
+        assert!(world.is_thing(pool));
+        assert!(world.has_flag(pool, Flag::Scenery));
+        assert!(world.has_flag(pool, Flag::Immovable));
+        assert!(world.has_prose_type(pool, ProseType::Scenery));
+        assert!(world.has_prose_type(pool, ProseType::Thing));
+        assert_eq!(phys::loc(&world, pool), world.lookup("here"));
+    }
+
+    #[test]
+    fn allow_and_on() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("note", "note", "note").location("here").on_read("Hello.");
+        wb.allow(&WBEvent::ReadThing("note")).unless(&|_| true).print("No.");
+        wb.on(&WBEvent::ReadThing("note")).once_only().print("Yes.");
+        let world = wb.world();
+        let pid = world.pid;
+        let note = world.lookup("note");
+
+        let guard = &world.rules[&world.lookup("allow-read-note")];
+        assert!(guard.is_guard);
+        assert_eq!(guard.event, Event::ReadThing(pid, note));
+
+        let rule = world.lookup("on-read-note");
+        assert!(!world.rules[&rule].is_guard);
+        assert_eq!(world.rules[&rule].limit, Some(1));
+    }
+
+    #[test]
+    fn thing_inside_thing() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("coin", "coin", "coin").inside("chest");
+        wb.thing("chest", "chest", "chest").location("here");
+        let world = wb.world();
+        let chest = world.lookup("chest");
+
+        assert_eq!(phys::loc(&world, world.lookup("coin")), chest);
+        assert!(world.has_inventory(chest));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected thing: [2] here")]
+    fn thing_inside_room() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("coin", "coin", "coin").inside("here");
+        wb.world();
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot set 'unless' predicate on normal rule")]
+    fn on_unless() {
+        let mut wb = WorldBuilder::new();
+        wb.room("here", "Here");
+        wb.on(&WBEvent::EnterRoom("here")).unless(&|_| true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined entity: [4] altr")]
+    fn custom_event_misspelled_tag() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.thing("altar", "altar", "altar").location("here");
+        wb.room("here", "Here");
+        wb.on(&WBEvent::Custom("ritual_complete", "altr")).print("The altar glows.");
+        wb.world();
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected thing: [4] swrod")]
+    fn on_misspelled_tag() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here");
+        wb.thing("sword", "sword", "sword").location("here");
+        wb.on(&WBEvent::GetThing("swrod")).print("Got it.");
+        wb.world();
+    }
+
+    #[test]
+    #[should_panic(expected = "easter egg shadows existing verb: look")]
+    fn easter_egg_shadows_verb() {
+        let mut wb = WorldBuilder::new();
+        wb.easter_egg("look", "Don't look now.");
+    }
+
+    #[test]
+    fn warnings_duplicate_room() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here").link(Dir::North, "there");
+        wb.room("there", "There");
+        assert!(wb.warnings().is_empty());
+
+        wb.room("here", "Here Again");
+        assert_eq!(wb.warnings(), vec!["Entity defined more than once: [2] here"]);
+
+        let events = visual::capture(|| {
+            wb.world();
+        });
+        assert_eq!(
+            events,
+            vec![visual::OutputEvent::Error("Warning: Entity defined more than once: [2] here".into())]
+        );
+    }
+
+    #[test]
+    fn link_both() {
+        let mut wb = WorldBuilder::new();
+        wb.player().location("here");
+        wb.room("here", "Here").link_both(Dir::North, "there");
+        wb.room("there", "There").link(Dir::Up, "here");
+        let world = wb.world();
+        let here = world.lookup("here");
+        let there = world.lookup("there");
+
+        assert!(matches!(phys::follow_link(&world, here, Dir::North), Some(LinkDest::Room(id)) if id == there));
+        assert!(matches!(phys::follow_link(&world, there, Dir::South), Some(LinkDest::Room(id)) if id == here));
+    }
+
+    #[test]
+    #[should_panic(expected = "Player has no initial location")]
+    fn world_no_player_location() {
+        let mut wb = WorldBuilder::new();
+        wb.room("here", "Here");
+        wb.world();
+    }
+}